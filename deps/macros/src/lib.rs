@@ -6,6 +6,16 @@ use utils::IntoError;
 
 const ATTR_MACRO_NAME: &'static str = "simple_op";
 
+/// Implements an operation that pops `input_arity` numbers and pushes one. The closure of each
+/// variant gets the popped numbers, deepest first, and can name their backend as `N`.
+///
+/// When an input is an array, the closure is run on each of its elements, taking plain numbers
+/// among the inputs for every element, and the results are pushed as an array.
+///
+/// With `dimensional = true`, inputs may also be quantities with units. The enum then implements
+/// `crate::units::Dimensional`, which picks the units the numbers are converted to before the
+/// closure runs and the unit of its result, and `crate::time::Temporal`, which says whether it
+/// takes dates and what its result on them is.
 #[allow(non_snake_case)]
 #[proc_macro_derive(SimpleOp)]
 pub fn ops_derive_SimpleOp(input_stream: TokenStream) -> TokenStream {
@@ -40,7 +50,7 @@ pub fn ops_derive_SimpleOp(input_stream: TokenStream) -> TokenStream {
                 (None,_) => Err(p?.into_error("Expected name to be only ident, not also path")),
                 (Some(key @ ( "SHORT_NAME" | "NAME" | "DESCRIPTION")),syn::Lit::Str(s)) => Ok((key.to_string(),KeyValue::String(s.value()))),
                 (Some("INPUT_ARITY"),syn::Lit::Int(i)) => Ok(("INPUT_ARITY".to_string(),KeyValue::Usize(i.base10_parse()?))),
-                (Some("EXCLUDE_FROMSTR"),syn::Lit::Bool(b)) => Ok(("EXCLUDE_FROMSTR".to_string(),KeyValue::Bool(b.value))),
+                (Some(key @ ("EXCLUDE_PARSE" | "DIMENSIONAL")),syn::Lit::Bool(b)) => Ok((key.to_string(),KeyValue::Bool(b.value))),
                 _ => Err(p?.into_error("Combination Name and Value not recoognized"))
             }).collect::<Result<std::collections::HashMap<String,_>,_>>()?;
         let short_name = enum_props.get("SHORT_NAME")
@@ -60,14 +70,15 @@ pub fn ops_derive_SimpleOp(input_stream: TokenStream) -> TokenStream {
             KeyValue::Usize(i) => *i,
             _ => unreachable!()
         }).ok_or(self_ident.into_error("Input Arity not specified in enum attributes"))?;
-        let exclude_fromstr = enum_props.get("EXCLUDE_FROMSTR").map(|v| match v {
+        let exclude_parse = enum_props.get("EXCLUDE_PARSE").map(|v| match v {
+            KeyValue::Bool(b) => *b,
+            _ => unreachable!()
+        }).unwrap_or(false);
+        let dimensional = enum_props.get("DIMENSIONAL").map(|v| match v {
             KeyValue::Bool(b) => *b,
             _ => unreachable!()
         }).unwrap_or(false);
 
-        let construct_vec_args = syn::punctuated::Punctuated
-            ::<TokenStream2,syn::token::Comma>
-            ::from_iter(vec![quote::quote!(stack.pop().unwrap());input_arity]);
         let destruct_vec_args = syn::punctuated::Punctuated
             ::<TokenStream2,syn::token::Comma>
             ::from_iter(vec![quote::quote!(args .pop().unwrap());input_arity]);
@@ -93,12 +104,31 @@ pub fn ops_derive_SimpleOp(input_stream: TokenStream) -> TokenStream {
         ))).collect::<Result<std::collections::HashMap<_,_>,syn::Error>>()?;
         let variants_iter = variant_to_closure_map.keys().map(|v| v.clone()).collect::<Vec<_>>();
         let variants_str_iter = variant_to_closure_map.keys().map(|i| i.to_string().trim().to_uppercase()).collect::<Vec<_>>();
+        let variants_source_iter = variant_to_closure_map.keys().map(|i| i.to_string().trim().to_lowercase()).collect::<Vec<_>>();
         let closures_iter = variant_to_closure_map.values().map(|v| v.clone()).collect::<Vec<_>>();
         let command_desc = quote::quote!(crate::CommandDesc);
         let command = quote::quote!(crate::Command);
-        let from_str = quote::quote!(::std::str::FromStr);
+        let parse = quote::quote!(crate::Parse);
+        let stmt = quote::quote!(crate::ast::Stmt);
         let result = quote::quote!(::std::result::Result);
         let error = quote::quote!(crate::error::Error);
+        let context = quote::quote!(crate::context::Context);
+        let value = quote::quote!(crate::value::Value);
+        let arity = quote::quote!(crate::check::Arity);
+        let number = quote::quote!(crate::number::Number);
+        let (args,push) = if dimensional {
+            (quote::quote!(
+                let (values,dated) = crate::time::undate(&self,&stack[stack.len() - #input_arity ..])?;
+                let (args,unit) = crate::units::convert(&self,&values)?;
+                let mut args = args.into_iter().rev().collect::<::std::vec::Vec::<N>>();
+            ),quote::quote!(crate::time::redate(dated,crate::units::quantity(out,unit))?))
+        } else {
+            (quote::quote!(
+                let mut args = stack[stack.len() - #input_arity ..].iter().rev()
+                    .map(#value :: as_num)
+                    .collect::<#result <::std::vec::Vec::<N>, #error >>()?;
+            ),quote::quote!(#value :: Num(out)))
+        };
         let mut out = quote::quote!(
             impl #command_desc for #self_ident {
                 const SHORT_NAME: Option<&'static str> = #short_name;
@@ -106,27 +136,56 @@ pub fn ops_derive_SimpleOp(input_stream: TokenStream) -> TokenStream {
                 const DESCRIPTION: &'static str = #description;
             }
 
+            impl #arity for #self_ident {
+                const ARITY: (usize,usize) = (#input_arity,1);
+            }
+
             impl #command for #self_ident {
-                fn comm(self, stack: &mut Vec<f64>, _stdin: impl ::std::io::Read, _stdout: impl ::std::io::Write) -> #result <Option<String>, #error > {
+                fn comm<N: #number>(self, stack: &mut Vec<#value<N>>, _ctx: &mut #context<N>, _stdin: impl ::std::io::Read, _stdout: impl ::std::io::Write) -> #result <Option<String>, #error > {
                     if stack.len() < #input_arity {
                         return Err(#error :: StackEmpty(stack.len(), #input_arity ));
                     }
-                    let mut args: ::std::vec::Vec::<f64> = vec![#construct_vec_args];
-                    stack.push(match self {
+                    if stack[stack.len() - #input_arity ..].iter().any(|v| matches!(v, #value :: Array(_))) {
+                        let out = crate::array::elementwise(&stack[stack.len() - #input_arity ..],|mut args: ::std::vec::Vec<N>| {
+                            args.reverse();
+                            match self {
+                                #( #self_ident :: #variants_iter => (#closures_iter)(#destruct_vec_args) ),*
+                            }
+                        })?;
+                        stack.truncate(stack.len() - #input_arity);
+                        stack.push(#value :: Array(out));
+                        return Ok(None);
+                    }
+                    #args
+                    let out = match self {
                         #( #self_ident :: #variants_iter => (#closures_iter)(#destruct_vec_args) ),*
-                    });
+                    };
+                    let out = #push;
+                    stack.truncate(stack.len() - #input_arity);
+                    stack.push(out);
                     Ok(None)
                 }
             }
         );
-        if !exclude_fromstr {
+        if !exclude_parse {
             out.extend(quote::quote!(
-                impl #from_str for #self_ident {
-                    type Err = #error;
-                    fn from_str(input: &str) -> #result <#self_ident,#error> {
-                        match input.to_uppercase().trim() {
-                            #( #variants_str_iter => Ok(#self_ident :: #variants_iter) ),* ,
-                            _ => Err(#error :: ParseToken( #name ))
+                impl #parse for #self_ident {
+                    const KEYWORDS: &'static [&'static str] = &[ #( #variants_str_iter ),* ];
+                    fn parse(stmt: & #stmt) -> #result <#self_ident,#error> {
+                        if !stmt.args().is_empty() {
+                            return Err(#error :: BadArgs( #name ));
+                        }
+                        match stmt.keyword().as_deref() {
+                            #( Some(#variants_str_iter) => Ok(#self_ident :: #variants_iter) ),* ,
+                            _ => Err(#error :: BadArgs( #name ))
+                        }
+                    }
+                }
+
+                impl ::std::fmt::Display for #self_ident {
+                    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                        match self {
+                            #( #self_ident :: #variants_iter => write!(f, #variants_source_iter) ),*
                         }
                     }
                 }
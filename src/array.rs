@@ -0,0 +1,230 @@
+use crate::error::*;
+use crate::number::Number;
+use crate::value::Value;
+
+/// Number of elements of a vector, or rows and columns of a matrix.
+#[derive(Clone,Copy,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum Shape {
+    Vector(usize),
+    Matrix(usize,usize)
+}
+
+impl Shape {
+    pub fn len(&self) -> usize {
+        match self {
+            Shape::Vector(n) => *n,
+            Shape::Matrix(rows,cols) => rows * cols
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shape::Vector(n) => write!(f,"{n}"),
+            Shape::Matrix(rows,cols) => write!(f,"{rows}x{cols}")
+        }
+    }
+}
+
+/// A vector or a matrix, with its elements stored row by row.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Array<N> {
+    pub shape: Shape,
+    pub data: Vec<N>
+}
+
+impl<N: Number> Array<N> {
+    fn matrix(rows: Vec<Vec<N>>) -> Array<N> {
+        let shape = Shape::Matrix(rows.len(),rows.first().map_or(0,Vec::len));
+        Array { shape, data: rows.into_iter().flatten().collect() }
+    }
+
+    fn rows(&self) -> Vec<Vec<N>> {
+        match self.shape {
+            Shape::Vector(_) => self.data.iter().map(|n| vec![n.clone()]).collect(),
+            Shape::Matrix(_,cols) => self.data.chunks(cols.max(1)).map(<[N]>::to_vec).collect()
+        }
+    }
+
+    /// Rows of a square matrix.
+    fn square(&self) -> Result<Vec<Vec<N>>> {
+        match self.shape {
+            Shape::Matrix(rows,cols) if rows == cols => Ok(self.rows()),
+            shape => Err(Error::Shape("a square matrix".to_string(),shape.to_string()))
+        }
+    }
+
+    /// Swaps rows and columns of a matrix. Vectors are left as they are.
+    pub fn transpose(self) -> Array<N> {
+        match self.shape {
+            Shape::Vector(_) => self,
+            Shape::Matrix(rows,cols) => Array {
+                shape: Shape::Matrix(cols,rows),
+                data: (0..cols).flat_map(|c| (0..rows).map(move |r| (r,c))).map(|(r,c)| self.data[r * cols + c].clone()).collect()
+            }
+        }
+    }
+
+    pub fn det(&self) -> Result<N> {
+        let mut rows = self.square()?;
+        let n = rows.len();
+        let mut det = N::from_f64(1.0);
+        for k in 0..n {
+            let Some(pivot) = pivot(&rows,k) else { return Ok(N::from_f64(0.0)) };
+            if pivot != k {
+                rows.swap(pivot,k);
+                det = N::from_f64(0.0) - det;
+            }
+            det = det * rows[k][k].clone();
+            for r in k + 1..n {
+                let factor = rows[r][k].clone() / rows[k][k].clone();
+                rows[r] = eliminate(&rows[r],&rows[k],factor);
+            }
+        }
+        Ok(det)
+    }
+
+    pub fn inv(&self) -> Result<Array<N>> {
+        let rows = self.square()?;
+        let n = rows.len();
+        let identity = (0..n).map(|r| (0..n).map(|c| N::from_f64(if r == c { 1.0 } else { 0.0 })).collect()).collect();
+        Ok(Array::matrix(gauss_jordan(rows,identity)?))
+    }
+
+    /// Solves `self * x = rhs` for `x`, where `rhs` is a vector or a matrix with a column for
+    /// each system to solve.
+    pub fn solve(&self, rhs: &Array<N>) -> Result<Array<N>> {
+        let rows = self.square()?;
+        if rhs.rows().len() != rows.len() {
+            return Err(Error::Shape(format!("{} rows",rows.len()),rhs.shape.to_string()));
+        }
+        let x = gauss_jordan(rows,rhs.rows())?;
+        Ok(match rhs.shape {
+            Shape::Vector(n) => Array { shape: Shape::Vector(n), data: x.into_iter().flatten().collect() },
+            Shape::Matrix(_,_) => Array::matrix(x)
+        })
+    }
+
+    /// Dot product of vectors, or matrix product when either is a matrix, where vectors are
+    /// taken as rows on the left and as columns on the right.
+    pub fn dot(&self, rhs: &Array<N>) -> Result<Value<N>> {
+        let lhs_rows = match self.shape {
+            Shape::Vector(_) => vec![self.data.clone()],
+            Shape::Matrix(_,_) => self.rows()
+        };
+        let rhs_rows = rhs.rows();
+        if lhs_rows.first().map_or(0,Vec::len) != rhs_rows.len() {
+            return Err(Error::Shape(format!("{} rows",lhs_rows.first().map_or(0,Vec::len)),rhs.shape.to_string()));
+        }
+        let cols = rhs_rows.first().map_or(0,Vec::len);
+        let product = lhs_rows.iter().map(|row| (0..cols).map(|c| {
+            row.iter().zip(&rhs_rows).fold(N::from_f64(0.0),|acc,(l,r)| acc + l.clone() * r[c].clone())
+        }).collect::<Vec<_>>()).collect::<Vec<_>>();
+        Ok(match (self.shape,rhs.shape) {
+            (Shape::Vector(_),Shape::Vector(_)) => Value::Num(product[0][0].clone()),
+            (Shape::Vector(_),_) => Value::Array(Array { shape: Shape::Vector(cols), data: product.into_iter().flatten().collect() }),
+            (_,Shape::Vector(_)) => Value::Array(Array { shape: Shape::Vector(product.len()), data: product.into_iter().flatten().collect() }),
+            _ => Value::Array(Array::matrix(product))
+        })
+    }
+
+    pub fn cross(&self, rhs: &Array<N>) -> Result<Array<N>> {
+        match (self.shape,rhs.shape) {
+            (Shape::Vector(3),Shape::Vector(3)) => {
+                let (a,b) = (&self.data,&rhs.data);
+                let term = |i: usize,j: usize| a[i].clone() * b[j].clone() - a[j].clone() * b[i].clone();
+                Ok(Array { shape: Shape::Vector(3), data: vec![term(1,2),term(2,0),term(0,1)] })
+            },
+            (Shape::Vector(3),shape) | (shape,_) => Err(Error::Shape("a vector of 3".to_string(),shape.to_string()))
+        }
+    }
+}
+
+/// Row from `k` down whose element in column `k` is the largest, which keeps elimination with
+/// floats accurate, or `None` if they are all zero.
+fn pivot<N: Number>(rows: &[Vec<N>], k: usize) -> Option<usize> {
+    (k..rows.len())
+        .filter(|r| !rows[*r][k].is_zero())
+        .max_by(|a,b| rows[*a][k].clone().abs().partial_cmp(&rows[*b][k].clone().abs()).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// Subtracts `factor` times `source` from `target`, element by element.
+fn eliminate<N: Number>(target: &[N], source: &[N], factor: N) -> Vec<N> {
+    target.iter().zip(source).map(|(t,s)| t.clone() - factor.clone() * s.clone()).collect()
+}
+
+/// Reduces `lhs` to the identity with row operations, applying the same ones to `rhs`, which
+/// gives `lhs^-1 * rhs`.
+fn gauss_jordan<N: Number>(mut lhs: Vec<Vec<N>>, mut rhs: Vec<Vec<N>>) -> Result<Vec<Vec<N>>> {
+    let n = lhs.len();
+    for k in 0..n {
+        let pivot = pivot(&lhs,k).ok_or(Error::Singular)?;
+        lhs.swap(pivot,k);
+        rhs.swap(pivot,k);
+        let p = lhs[k][k].clone();
+        lhs[k] = lhs[k].iter().map(|x| x.clone() / p.clone()).collect();
+        rhs[k] = rhs[k].iter().map(|x| x.clone() / p.clone()).collect();
+        for r in (0..n).filter(|r| *r != k) {
+            let factor = lhs[r][k].clone();
+            if factor.is_zero() {
+                continue;
+            }
+            lhs[r] = eliminate(&lhs[r],&lhs[k],factor.clone());
+            rhs[r] = eliminate(&rhs[r],&rhs[k],factor);
+        }
+    }
+    Ok(rhs)
+}
+
+/// Applies an operation on numbers to each element of arrays of the same shape, with plain
+/// numbers among the inputs taking part in every element. `f` gets the numbers deepest first.
+pub fn elementwise<N: Number>(values: &[Value<N>], mut f: impl FnMut(Vec<N>) -> N) -> Result<Array<N>> {
+    let mut shape = None;
+    for v in values {
+        match (v,shape) {
+            (Value::Num(_),_) => (),
+            (Value::Array(a),None) => shape = Some(a.shape),
+            (Value::Array(a),Some(s)) if a.shape == s => (),
+            (Value::Array(a),Some(s)) => return Err(Error::Shape(format!("an array of shape {s}"),a.shape.to_string())),
+            (v,_) => return Err(Error::Type("number or array",v.kind()))
+        }
+    }
+    let shape = shape.unwrap_or(Shape::Vector(0));
+    let data = (0..shape.len()).map(|i| f(values.iter().map(|v| match v {
+        Value::Array(a) => a.data[i].clone(),
+        v => v.as_num().unwrap_or_else(|_| N::from_f64(f64::NAN))
+    }).collect())).collect();
+    Ok(Array { shape, data })
+}
+
+/// The array at `v`, or a type error.
+pub fn array_of<N: Number>(v: &Value<N>) -> Result<&Array<N>> {
+    match v {
+        Value::Array(a) => Ok(a),
+        v => Err(Error::Type("array",v.kind()))
+    }
+}
+
+/// Shows elements without the padding numbers have on their own, as in `[2, -1]`.
+impl<N: Number> std::fmt::Display for Array<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let element = |n: &N| {
+            let n = n.format();
+            match n.trim().strip_prefix('-') {
+                Some(abs) => format!("-{}",abs.trim_start()),
+                None => n.trim().to_string()
+            }
+        };
+        let row = |row: &[N]| format!("[{}]",row.iter().map(element).intersperse(", ".to_string()).collect::<String>());
+        match self.shape {
+            Shape::Vector(_) => write!(f,"{}",row(&self.data)),
+            Shape::Matrix(_,_) => write!(f,"[{}]",self.rows().iter().map(|r| row(r)).intersperse(" ".to_string()).collect::<String>())
+        }
+    }
+}
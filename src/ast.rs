@@ -0,0 +1,93 @@
+use crate::span::Span;
+
+/// A whole script: the statements of a chain written without the enclosing brackets.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Program(pub Vec<Stmt>);
+
+/// Statements enclosed in square brackets.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Block(pub Vec<Stmt>);
+
+/// Everything between two semicolons, where it is in the source, and the comments written
+/// anywhere in that stretch of source or after its semicolon on the same line. An empty
+/// statement does nothing.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Stmt(pub Vec<Atom>,pub Span,pub Vec<Comment>);
+
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum Atom {
+    /// Value and text of a number, as in `1.5`
+    Num(f64,String),
+    /// Numerator and denominator of a fraction as written, as in `1/3`
+    Frac(String,String),
+    /// Real and imaginary parts of a complex number, as in `3+4i`
+    Complex(f64,f64),
+    /// Midpoint and radius of a range of numbers, as in `1.2±0.05`
+    PlusMinus(f64,f64),
+    /// Date, time of day or duration, as in `2026-10-17`, `14:30` or `1h30m`
+    Time(crate::time::Literal),
+    Word(String),
+    Str(String),
+    Block(Block),
+    /// Comma separated arguments in parentheses, as in `R(5)`
+    Group(Vec<Atom>)
+}
+
+/// Comment text without its delimiters, and where it is in the source.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum Comment {
+    /// `# ...` up to the end of the line
+    Line(String,Span),
+    /// `/* ... */`
+    Block(String,Span)
+}
+
+impl Stmt {
+    /// The upper-cased leading word, which selects the command this statement runs.
+    pub fn keyword(&self) -> Option<String> {
+        match self.0.first() {
+            Some(Atom::Word(w)) => Some(w.to_uppercase()),
+            _ => None
+        }
+    }
+
+    /// The atoms following the leading one.
+    pub fn args(&self) -> &[Atom] {
+        self.0.get(1..).unwrap_or(&[])
+    }
+}
+
+impl Comment {
+    pub fn span(&self) -> Span {
+        match self {
+            Comment::Line(_,span) | Comment::Block(_,span) => *span
+        }
+    }
+}
+
+impl std::fmt::Display for Comment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comment::Line(text,_) => write!(f,"#{text}"),
+            Comment::Block(text,_) => write!(f,"/*{text}*/")
+        }
+    }
+}
+
+impl Atom {
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Atom::Num(n,_) if n.fract() == 0.0 && *n >= 0.0 && *n <= usize::MAX as f64 => Some(*n as usize),
+            _ => None
+        }
+    }
+
+    pub fn is_word(&self, expected: &str) -> bool {
+        matches!(self, Atom::Word(w) if w.to_uppercase() == expected)
+    }
+}
@@ -0,0 +1,181 @@
+use std::ops::{Add,Sub,Mul,Div};
+use num_bigint::BigInt;
+use num_traits::{Zero,Signed,ToPrimitive,FromPrimitive};
+use crate::error::*;
+use crate::number::Number;
+
+/// Largest integer such that floats hold it and every integer below it exactly, 2^53 - 1
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Largest number of bits `pow` computes an exact integer result with before falling back to
+/// floating point, about 300 000 digits
+const MAX_EXACT_BITS: u64 = 1_000_000;
+
+/// Number of the float mode: a float, or a big integer once integer arithmetic outgrows the range
+/// where floats hold every integer, so that large powers and factorials keep all their digits.
+#[derive(Clone,Debug)]
+pub enum Big {
+    Float(f64),
+    /// Integer beyond the safe range of floats
+    Int(BigInt)
+}
+
+impl Big {
+    /// Holds an integer as a float when that is exact.
+    fn int(n: BigInt) -> Big {
+        match n.to_f64() {
+            Some(f) if f.abs() <= MAX_SAFE_INTEGER => Big::Float(f),
+            _ => Big::Int(n)
+        }
+    }
+
+    /// The number as an exact integer, if it is one. Floats past the safe range are not, as
+    /// they were likely rounded.
+    fn as_int(&self) -> Option<BigInt> {
+        match self {
+            Big::Int(n) => Some(n.clone()),
+            Big::Float(f) if f.fract() == 0.0 && f.abs() <= MAX_SAFE_INTEGER => BigInt::from_f64(*f),
+            Big::Float(_) => None
+        }
+    }
+
+    /// Applies an operation to floats, redoing it on big integers when both inputs are exact
+    /// integers but the result is past the safe range.
+    fn apply(self, rhs: Big, float: impl Fn(f64,f64) -> f64, int: impl Fn(BigInt,BigInt) -> Option<BigInt>) -> Big {
+        let result = float(self.to_f64(),rhs.to_f64());
+        if let (Big::Float(_),Big::Float(_)) = (&self,&rhs) {
+            if result.abs() <= MAX_SAFE_INTEGER {
+                return Big::Float(result);
+            }
+        }
+        match (self.as_int(),rhs.as_int()) {
+            (Some(lhs),Some(rhs)) => int(lhs,rhs).map_or(Big::Float(result),Big::int),
+            _ => Big::Float(result)
+        }
+    }
+}
+
+impl Number for Big {
+    fn from_f64(n: f64) -> Self {
+        Big::Float(n)
+    }
+
+    fn to_f64(&self) -> f64 {
+        match self {
+            Big::Float(n) => *n,
+            Big::Int(n) => n.to_f64().unwrap_or(f64::NAN)
+        }
+    }
+
+    /// Integers written past the safe range are read as exactly what they print as.
+    fn from_literal(n: f64) -> Self {
+        if n.abs() <= MAX_SAFE_INTEGER || !n.is_finite() {
+            return Big::Float(n);
+        }
+        n.to_string().parse::<BigInt>().map_or(Big::Float(n),Big::Int)
+    }
+
+    /// Integers written past the safe range keep every digit of their text.
+    fn from_source(text: &str) -> Self {
+        let n = text.parse::<f64>().unwrap_or(f64::NAN);
+        if n.abs() <= MAX_SAFE_INTEGER {
+            return Big::Float(n);
+        }
+        match crate::rational::Rational::decimal(text).filter(|r| r.is_integer()) {
+            Some(r) => Big::int(r.to_integer()),
+            None => Big::Float(n)
+        }
+    }
+
+    fn to_literal(&self) -> Option<f64> {
+        match self {
+            Big::Float(n) => Some(*n),
+            Big::Int(int) => {
+                let n = self.to_f64();
+                matches!(Big::from_literal(n), Big::Int(back) if back == *int).then_some(n)
+            }
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        match s.parse::<BigInt>() {
+            Ok(n) => Ok(Big::int(n)),
+            Err(_) => Ok(Big::Float(s.parse::<f64>()?))
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        match self {
+            Big::Float(n) => *n == 0.0,
+            Big::Int(_) => false
+        }
+    }
+
+    /// Shows big integers with all their digits.
+    fn format(&self) -> String {
+        match self {
+            Big::Float(n) => crate::format_num(n),
+            Big::Int(n) => format!("{}{}",if n.is_negative() { "-" } else { " " },crate::group_digits(&n.abs().to_string()))
+        }
+    }
+
+    fn powf(self, exp: Self) -> Self {
+        self.apply(exp,f64::powf,|base,exp| {
+            let exp = exp.to_u32()?;
+            (base.bits().saturating_mul(exp as u64) <= MAX_EXACT_BITS).then(|| base.pow(exp))
+        })
+    }
+}
+
+impl Add for Big {
+    type Output = Big;
+    fn add(self, rhs: Big) -> Big {
+        self.apply(rhs,|a,b| a + b,|a,b| Some(a + b))
+    }
+}
+
+impl Sub for Big {
+    type Output = Big;
+    fn sub(self, rhs: Big) -> Big {
+        self.apply(rhs,|a,b| a - b,|a,b| Some(a - b))
+    }
+}
+
+impl Mul for Big {
+    type Output = Big;
+    fn mul(self, rhs: Big) -> Big {
+        self.apply(rhs,|a,b| a * b,|a,b| Some(a * b))
+    }
+}
+
+impl Div for Big {
+    type Output = Big;
+    /// Stays an integer only when the division is exact.
+    fn div(self, rhs: Big) -> Big {
+        self.apply(rhs,|a,b| a / b,|a,b| (!b.is_zero() && (&a % &b).is_zero()).then(|| a / b))
+    }
+}
+
+impl PartialEq for Big {
+    fn eq(&self, other: &Big) -> bool {
+        match (self,other) {
+            (Big::Float(lhs),Big::Float(rhs)) => lhs == rhs,
+            (lhs,rhs) => match (lhs.as_int(),rhs.as_int()) {
+                (Some(lhs),Some(rhs)) => lhs == rhs,
+                _ => lhs.to_f64() == rhs.to_f64()
+            }
+        }
+    }
+}
+
+impl PartialOrd for Big {
+    fn partial_cmp(&self, other: &Big) -> Option<std::cmp::Ordering> {
+        match (self,other) {
+            (Big::Float(lhs),Big::Float(rhs)) => lhs.partial_cmp(rhs),
+            (lhs,rhs) => match (lhs.as_int(),rhs.as_int()) {
+                (Some(lhs),Some(rhs)) => lhs.partial_cmp(&rhs),
+                _ => lhs.to_f64().partial_cmp(&rhs.to_f64())
+            }
+        }
+    }
+}
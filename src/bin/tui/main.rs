@@ -1,7 +1,4 @@
 #![feature(trivial_bounds)]
-#![feature(macro_metavar_expr)]
-#![feature(array_chunks)]
-#![feature(iter_intersperse)]
 
 use std::io::Write;
 use std::io::BufRead;
@@ -12,33 +9,175 @@ use tui_error::*;
 mod input;
 
 use rpncalc::Command;
+use rpncalc::number::Number;
+use rpncalc::value::Value;
 
 #[derive(clap::Parser)]
 #[clap(version,about,author)]
 struct Main {
     /// Executes a file instead of opening the REPL. Format of the file is a UTF-8 'chain' command as shown in the REPL, except no need to type the enclosing square brackets.
-    file: Option<std::path::PathBuf>
+    file: Option<std::path::PathBuf>,
+    /// How deeply words defined with 'def' may call each other or themselves before failing.
+    #[clap(long)]
+    max_depth: Option<usize>,
+    /// Rewrites the file in canonical form, with lowercase keywords and nested blocks indented, instead of executing it.
+    #[clap(long, requires = "file")]
+    fmt: bool,
+    /// Prints the parsed program as JSON instead of executing it.
+    #[clap(long, requires = "file", conflicts_with = "fmt")]
+    dump_ast: bool,
+    /// Reports where the stack could run out of values, or where the branches of an if leave it at different depths, instead of executing the file.
+    #[clap(long, requires = "file", conflicts_with_all = &["fmt", "dump-ast"])]
+    check: bool,
+    #[clap(flatten)]
+    numbers: Numbers
+}
+
+#[derive(clap::Parser)]
+struct Numbers {
+    /// How numbers are stored and computed. A file can also set this, --scale and --rounding with a line comment like `#pragma --mode decimal --scale 4`, which options given here override. [default: float]
+    #[clap(long, value_enum)]
+    mode: Option<Mode>,
+    /// Decimals kept by the decimal mode, up to 1000. [default: 2]
+    #[clap(long, value_parser = clap::value_parser!(u32).range(..=i64::from(rpncalc::decimal::MAX_SCALE)))]
+    scale: Option<u32>,
+    /// How the decimal mode rounds results to its scale. [default: half-even]
+    #[clap(long, value_enum)]
+    rounding: Option<Rounding>
+}
+
+impl Numbers {
+    /// Fills in the options not given with those of `other`.
+    fn or(self, other: Numbers) -> Numbers {
+        Numbers {
+            mode: self.mode.or(other.mode),
+            scale: self.scale.or(other.scale),
+            rounding: self.rounding.or(other.rounding)
+        }
+    }
+
+    /// Reads the options of the pragma comments in a file, later ones taking precedence.
+    fn from_pragmas(source: &str) -> Result<Numbers> {
+        use clap::Parser;
+        let pragmas = rpncalc::lexer::Lexer::new(source).filter_map(|t| match t {
+            Ok((rpncalc::lexer::Token::Comment(rpncalc::ast::Comment::Line(text,_)),_)) => text.strip_prefix("pragma ").map(str::to_string),
+            _ => None
+        });
+        let mut numbers = Numbers { mode: None, scale: None, rounding: None };
+        for pragma in pragmas {
+            numbers = Numbers::try_parse_from(std::iter::once("pragma").chain(pragma.split_whitespace()))?.or(numbers);
+        }
+        Ok(numbers)
+    }
+}
+
+#[derive(Clone,clap::ValueEnum)]
+enum Mode {
+    /// Floating point numbers, switching to big integers when integer arithmetic outgrows the precision of floats
+    Float,
+    /// Exact fractions through arithmetic and comparisons, falling back to floating point for operations with irrational results
+    Rational,
+    /// Complex numbers, entered as 3+4i or (3,4), so that square roots, logarithms and powers of negative numbers have results
+    Complex,
+    /// Base 10 numbers with a fixed number of decimals, rounding every result to it, for amounts of money
+    Decimal,
+    /// Bounds guaranteed to hold the exact result, entered as 1.2±0.05, showing how much error a computation accumulates
+    Interval,
+    /// Measurements with a standard deviation, entered as 12.3±0.4, propagating it through every operation to first order
+    Uncertainty
+}
+
+#[derive(Clone,clap::ValueEnum)]
+enum Rounding {
+    /// To the nearest, and ties to an even last digit
+    HalfEven,
+    /// To the nearest, and ties away from zero
+    HalfUp,
+    /// Towards zero
+    Truncate
 }
 
 fn main() -> Result {
     use clap::Parser;
     let Main {
-        file
+        file,
+        max_depth,
+        fmt,
+        dump_ast,
+        check,
+        numbers
     } = Main::parse();
-    let mut stack = vec![];
+    if let Some(p) = file.as_ref().filter(|_| fmt || dump_ast || check) {
+        let file_name = p.display().to_string();
+        let file_bytes = std::fs::read(p)?;
+        let file_str = std::str::from_utf8(&file_bytes)?;
+        let chain = rpncalc::Chain::from_bare(file_str).map_err(|e| e.with_source(&file_name,file_str))?;
+        if fmt {
+            let mut formatted = chain.to_string();
+            if !formatted.ends_with('\n') {
+                formatted.push('\n');
+            }
+            std::fs::write(p,formatted)?;
+            return Ok(());
+        }
+        if dump_ast {
+            println!("{}",serde_json::to_string_pretty(&chain)?);
+            return Ok(());
+        }
+        let problems = rpncalc::check::check(&chain,0);
+        let count = problems.len();
+        for problem in problems {
+            println!("{}\n",problem.with_source(&file_name,file_str));
+        }
+        return match count {
+            0 => Ok(()),
+            n => Err(Error::Check(n))
+        };
+    }
+    let numbers = match &file {
+        Some(p) => numbers.or(Numbers::from_pragmas(&String::from_utf8_lossy(&std::fs::read(p)?))?),
+        None => numbers
+    };
+    rpncalc::decimal::configure(rpncalc::decimal::Settings {
+        scale: numbers.scale.unwrap_or(2),
+        rounding: match numbers.rounding.unwrap_or(Rounding::HalfEven) {
+            Rounding::HalfEven => rpncalc::decimal::Rounding::HalfEven,
+            Rounding::HalfUp => rpncalc::decimal::Rounding::HalfUp,
+            Rounding::Truncate => rpncalc::decimal::Rounding::Truncate
+        }
+    });
+    match numbers.mode.unwrap_or(Mode::Float) {
+        Mode::Float => run::<rpncalc::big::Big>(file,max_depth),
+        Mode::Rational => run::<rpncalc::rational::Rational>(file,max_depth),
+        Mode::Complex => run::<rpncalc::complex::Complex>(file,max_depth),
+        Mode::Decimal => run::<rpncalc::decimal::Decimal>(file,max_depth),
+        Mode::Interval => run::<rpncalc::interval::Interval>(file,max_depth),
+        Mode::Uncertainty => run::<rpncalc::uncertainty::Uncertain>(file,max_depth)
+    }
+}
+
+/// Executes the file, or opens the REPL if there is none, with numbers of the backend `N`.
+fn run<N: Number>(file: Option<std::path::PathBuf>, max_depth: Option<usize>) -> Result {
+    let mut stack: Vec<Value<N>> = vec![];
+    let mut ctx = rpncalc::context::Context::<N>::default();
+    if let Some(max_depth) = max_depth {
+        ctx.max_depth = max_depth;
+    }
     match file {
         Some(p) => {
-            let file_bytes = std::fs::read(p)?;
+            let file_name = p.display().to_string();
+            ctx.files.push(std::fs::canonicalize(&p)?);
+            let file_bytes = std::fs::read(&p)?;
             let file_str = std::str::from_utf8(&file_bytes)?;
             let stdin = std::io::stdin();
             let mut stdin_lock = stdin.lock();
             let stdout = std::io::stdout();
             let mut stdout_lock = stdout.lock();
-            match rpncalc::Chain::from_bare(file_str)?.comm(&mut stack,&mut stdin_lock,&mut stdout_lock) {
+            let chain = rpncalc::Chain::from_bare(file_str).map_err(|e| e.with_source(&file_name,file_str))?;
+            match rpncalc::vm::Code::compile(&rpncalc::optimize::optimize::<N>(chain)).comm(&mut stack,&mut ctx,&mut stdin_lock,&mut stdout_lock) {
                 Ok(msg_opt) => {
-                    match msg_opt {
-                        Some(msg) => println!("{msg}"),
-                        None => ()
+                    if let Some(msg) = msg_opt {
+                        println!("{msg}");
                     }
                     println!("Exited successfully with the following stack:");
                     let stdout = std::io::stdout();
@@ -53,7 +192,7 @@ fn main() -> Result {
                     print_stack(&stack, &mut stdout_lock)?;
                     std::mem::drop(stdout_lock);
                     println!("And with the following error:");
-                    Err(e.into())
+                    Err(e.with_source(&file_name,file_str).into())
                 }
             }
         },
@@ -75,7 +214,8 @@ fn main() -> Result {
                 let mut stdin_lock = stdin.lock();
                 stdin_lock.read_line(&mut input_buf)?;
                 use input::*;
-                let input_res = input_buf.trim().parse::<Input>();
+                let input_str = input_buf.trim();
+                let input_res = input_str.parse::<Input>();
                 match input_res {
                     Ok(input) => prev_msg_op = match input {
                         Input::Exit => break,
@@ -92,12 +232,12 @@ fn main() -> Result {
                         }
                         Input::CommandOrOp(c) => {
                             prev_stack.clone_from(&stack);
-                            match c.comm(&mut stack, &mut stdin_lock, &mut stdout_lock) {
+                            match rpncalc::vm::Code::compile(&rpncalc::Chain(vec![c])).comm(&mut stack, &mut ctx, &mut stdin_lock, &mut stdout_lock) {
                                 Ok(new_msg_op) => 
                                     new_msg_op,
                                 Err(e) => {
                                     stack.clone_from(&prev_stack);
-                                    Some(format!("Error executing last command, reverting stack: \n{0}",e))
+                                    Some(format!("Error executing last command, reverting stack: \n{0}",e.with_source("<input>",input_str)))
                                 },
                             }
                         }
@@ -117,11 +257,10 @@ fn main() -> Result {
     }
 }
 
-fn print_stack(stack: &[f64], mut w: impl Write) -> Result {
-    if stack.len() > 0 {
+fn print_stack<N: Number>(stack: &[Value<N>], mut w: impl Write) -> Result {
+    if !stack.is_empty() {
         for (index,elm) in stack.iter().rev().enumerate().rev() {
-            let num_formatted = rpncalc::format_num(*elm);
-            writeln!(w,"{:3}: {}",index,num_formatted)?;
+            writeln!(w,"{:3}: {}",index,elm)?;
         }
     } else {
         writeln!(w, "<Empty Stack>")?;
@@ -1,19 +1,26 @@
-#[derive(Debug,thiserror::Error)]
+#[derive(thiserror::Error)]
 pub enum Error {
-    #[error("Input string could not be parsed as the exit command")]
-	Exit,
-    #[error("Input string could not be parsed as the help command")]
-    Help,
-    #[error("Failed to parse as any command or operation: {0:?}")]
-	Parse(Vec<Error>),
-    #[error("Error from rpncalc library")]
-    LibError(#[from] rpncalc::error::Error),
+    #[error("{0}")]
+    Lib(#[from] rpncalc::error::Error),
     #[error("Error reading UTF-8")]
     UTF8(#[from] std::str::Utf8Error),
     #[error("Error during IO: \n{0}")]
     IO(#[from] std::io::Error),
     #[error("Error clearing screen: \n{0}")]
     ClearScreen(#[from] clearscreen::Error),
+    #[error("Found {0} stack problems")]
+    Check(usize),
+    #[error("Invalid pragma: \n{0}")]
+    Pragma(#[from] clap::Error),
+    #[error("Error serializing to JSON: \n{0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Shown when `main` returns an error, so library diagnostics keep their formatting.
+impl std::fmt::Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self,f)
+    }
 }
 
 pub type Result<T = (), E = Error> = std::result::Result<T,E>;
\ No newline at end of file
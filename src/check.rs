@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use crate::error::*;
+use crate::span::Span;
+use crate::{Chain,CommandOrOp,CommandEnum,Conditional,ConditionalKind,Repeat,Register,Combinator,Segment};
+
+/// Values an operation pops, and values it pushes in their place.
+pub trait Arity {
+    const ARITY: (usize,usize);
+}
+
+/// How many values are on the stack at some point of a chain, if that can be known before
+/// running it.
+type Depth = Option<usize>;
+
+/// Stack depth assumed when it is unknown but the change a chain makes to it is still wanted,
+/// deep enough that nothing in a real script runs out of values from it.
+const BASELINE: usize = 1 << 20;
+
+/// Infers the stack depth through a chain without running it, reporting every command that
+/// could run out of values and every `if` whose branches leave the stack at different depths.
+/// `depth` is how many values the stack holds when the chain starts.
+pub fn check(chain: &Chain, depth: usize) -> Vec<Error> {
+    let mut checker = Checker { words: HashMap::new(), calling: vec![], problems: vec![] };
+    checker.chain(chain,Some(depth));
+    checker.problems
+}
+
+struct Checker<'a> {
+    /// Words defined so far, keyed by their upper-cased name
+    words: HashMap<String,&'a Chain>,
+    /// Words whose body is being checked, to stop at recursive calls
+    calling: Vec<String>,
+    problems: Vec<Error>
+}
+
+impl<'a> Checker<'a> {
+    /// Runs `f` without reporting anything, returning the problems it found instead.
+    fn quietly<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> (T,Vec<Error>) {
+        let problems = std::mem::take(&mut self.problems);
+        let out = f(self);
+        (out,std::mem::replace(&mut self.problems,problems))
+    }
+
+    /// Pops `n` values, reporting it if the stack could hold fewer. The depth is unknown past an
+    /// underflow, so that a single missing value is not reported again by everything after it.
+    fn pop(&mut self, depth: Depth, n: usize, span: Span) -> Depth {
+        match depth {
+            Some(d) if d < n => {
+                self.problems.push(Error::Underflow(n,d).at(span));
+                None
+            },
+            depth => depth.map(|d| d - n)
+        }
+    }
+
+    fn chain(&mut self, chain: &'a Chain, mut depth: Depth) -> Depth {
+        for c in &chain.0 {
+            depth = self.command(c,depth);
+        }
+        depth
+    }
+
+    fn command(&mut self, c: &'a CommandOrOp, depth: Depth) -> Depth {
+        let span = c.span();
+        let c = match c {
+            CommandOrOp::Op(o,_,_) => {
+                let (pops,pushes) = o.arity();
+                return self.pop(depth,pops,span).map(|d| d + pushes);
+            },
+            CommandOrOp::Command(c,_,_) => c
+        };
+        match c {
+            CommandEnum::Def(crate::Def(name,body)) => {
+                self.words.insert(name.to_uppercase(),body);
+                depth
+            },
+            CommandEnum::Drop(crate::Drop::Some(0)) | CommandEnum::Reverse(_) | CommandEnum::Register(Register::Purge(_) | Register::List) => depth,
+            CommandEnum::Drop(crate::Drop::Some(n)) => self.pop(depth,1,span).map(|d| (d + 1).saturating_sub(*n)),
+            CommandEnum::Drop(crate::Drop::All) => Some(0),
+            CommandEnum::Dup(crate::Dup(n)) => self.pop(depth,1,span).map(|d| d + 1 + n),
+            CommandEnum::Pack(crate::Pack(shape)) => self.pop(depth,shape.len(),span).map(|d| d + 1),
+            CommandEnum::Swap(crate::Swap::LastTwo) => self.pop(depth,2,span).map(|d| d + 2),
+            CommandEnum::Swap(crate::Swap::Specified(from,to)) => {
+                let n = from.max(to) + 1;
+                self.pop(depth,n,span).map(|d| d + n)
+            },
+            CommandEnum::Input(_) | CommandEnum::Quote(_) | CommandEnum::Register(Register::Recall(_)) => depth.map(|d| d + 1),
+            CommandEnum::Print(_) | CommandEnum::Register(Register::Store(_)) => self.pop(depth,1,span),
+            CommandEnum::Display(crate::Display(segments)) => {
+                let n = segments.iter().filter_map(|s| match s {
+                    Segment::Stack(index) => Some(index + 1),
+                    _ => None
+                }).max().unwrap_or(0);
+                self.pop(depth,n,span).map(|d| d + n)
+            },
+            CommandEnum::Combinator(combinator) => {
+                let n = match combinator {
+                    Combinator::Exec => 1,
+                    Combinator::Dip | Combinator::Keep => 2,
+                    Combinator::Bi => 3
+                };
+                self.pop(depth,n,span);
+                None
+            },
+            CommandEnum::Break(_) | CommandEnum::Include(_) => None,
+            CommandEnum::Chain(chain) => self.chain(chain,depth),
+            CommandEnum::Call(crate::Call(name)) => {
+                let key = name.to_uppercase();
+                match self.words.get(&key) {
+                    Some(body) if !self.calling.contains(&key) => {
+                        self.calling.push(key);
+                        let (out,mut problems) = self.quietly(|s| s.chain(body,depth));
+                        self.calling.pop();
+                        if !problems.is_empty() {
+                            self.problems.push(Error::InWord(name.clone(),Box::new(problems.remove(0))).at(span));
+                        }
+                        out
+                    },
+                    _ => None
+                }
+            },
+            CommandEnum::Conditional(Conditional(ConditionalKind::If,[first,second])) => {
+                let depth = self.pop(depth,1,span);
+                let after = [first,second].map(|branch| self.chain(branch,depth));
+                let changes = match depth {
+                    Some(d) => after.map(|a| a.map(|a| a as isize - d as isize)),
+                    None => [first,second].map(|branch| self.quietly(|s| s.chain(branch,Some(BASELINE))).0.map(|a| a as isize - BASELINE as isize))
+                };
+                match changes {
+                    [Some(a),Some(b)] if a != b => {
+                        self.problems.push(Error::BranchDepths(a,b).at(span));
+                        None
+                    },
+                    _ if after[0] == after[1] => after[0],
+                    _ => None
+                }
+            },
+            CommandEnum::Conditional(Conditional(ConditionalKind::Try,[first,second])) => {
+                let first = self.chain(first,depth);
+                let second = self.chain(second,None);
+                if first == second { first } else { None }
+            },
+            CommandEnum::Repeat(Repeat::Bounded(n,body)) => self.repeat(depth,Some(*n),span,|s,d| s.command(body,d)),
+            CommandEnum::Repeat(Repeat::Unbounded(body)) => {
+                self.command(body,depth);
+                None
+            },
+            CommandEnum::Repeat(Repeat::While([cond,body])) => {
+                let after_cond = |s: &mut Self, d| {
+                    let d = s.chain(cond,d);
+                    s.pop(d,1,span)
+                };
+                let exit = self.quietly(|s| after_cond(s,depth)).0;
+                self.repeat(depth,None,span,|s,d| {
+                    let d = after_cond(s,d);
+                    s.chain(body,d)
+                }).and(exit)
+            },
+            CommandEnum::Repeat(Repeat::For([start,end,step],register,body)) => {
+                let reps = ((end - start) / step).floor() + 1.0;
+                if reps.is_nan() || reps >= crate::MAX_REPETITIONS as f64 {
+                    return None;
+                }
+                let pushes = register.is_none() as usize;
+                self.repeat(depth,Some(reps.max(0.0) as usize),span,|s,d| s.chain(body,d.map(|d| d + pushes)))
+            }
+        }
+    }
+
+    /// Checks a loop body run `n` times, or an unknown number of times if `None`. The first
+    /// iteration is checked as written. When each iteration leaves fewer values than it found,
+    /// the last one is checked too, as it starts from the shallowest stack.
+    fn repeat(&mut self, depth: Depth, n: Option<usize>, span: Span, mut body: impl FnMut(&mut Self, Depth) -> Depth) -> Depth {
+        if n == Some(0) {
+            return depth;
+        }
+        let first = body(self,depth);
+        let (Some(d),Some(after)) = (depth,first) else { return None };
+        let change = after as isize - d as isize;
+        match n {
+            _ if change == 0 => Some(d),
+            None => None,
+            Some(n) if change > 0 => Some(d + n * change as usize),
+            Some(n) => {
+                let used = (n - 1) * change.unsigned_abs();
+                let last_ok = d >= used && self.quietly(|s| body(s,Some(d - used))).1.is_empty();
+                if !last_ok {
+                    self.problems.push(Error::LoopUnderflow(n,d).at(span));
+                    return None;
+                }
+                Some(d - used - change.unsigned_abs())
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::ops::{Add,Sub,Mul,Div};
+use num_complex::Complex64;
+use crate::error::*;
+use crate::number::Number;
+
+/// Number of the complex mode. Operations on real numbers give the same results as in float
+/// mode while they stay in their domain, and complex results instead of NaN outside of it, so
+/// that the square root of -1 is i.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Complex(pub Complex64);
+
+impl Complex {
+    fn real(&self) -> Option<f64> {
+        (self.0.im == 0.0).then_some(self.0.re)
+    }
+
+    /// Computes a function with its real version when the number is real and inside the domain
+    /// of it, and with its complex version otherwise.
+    fn unary(self, real: fn(f64) -> f64, complex: fn(Complex64) -> Complex64) -> Complex {
+        match self.real().map(real) {
+            Some(n) if !n.is_nan() || self.0.re.is_nan() => Complex::from_f64(n),
+            _ => Complex(complex(self.0))
+        }
+    }
+
+    /// Like [`Complex::unary`], for functions of two numbers.
+    fn binary(self, rhs: Complex, real: fn(f64,f64) -> f64, complex: fn(Complex64,Complex64) -> Complex64) -> Complex {
+        match (self.real(),rhs.real()) {
+            (Some(lhs),Some(rhs)) if !real(lhs,rhs).is_nan() || lhs.is_nan() || rhs.is_nan() => Complex::from_f64(real(lhs,rhs)),
+            _ => Complex(complex(self.0,rhs.0))
+        }
+    }
+}
+
+impl Number for Complex {
+    fn from_f64(n: f64) -> Self {
+        Complex(Complex64::new(n,0.0))
+    }
+
+    /// The real part, or NaN for numbers that are not real.
+    fn to_f64(&self) -> f64 {
+        self.real().unwrap_or(f64::NAN)
+    }
+
+    fn from_parts(re: f64, im: f64) -> Option<Self> {
+        Some(Complex(Complex64::new(re,im)))
+    }
+
+    fn to_literal(&self) -> Option<f64> {
+        self.real()
+    }
+
+    /// Reads real numbers, and complex ones written as `3+4i` or `(3,4)`.
+    fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let pair = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')).and_then(|s| s.split_once(','));
+        if let Some((re,im)) = pair {
+            return Ok(Complex(Complex64::new(re.trim().parse()?,im.trim().parse()?)));
+        }
+        match crate::lexer::complex_literal(s) {
+            Some((re,im)) => Ok(Complex(Complex64::new(re,im))),
+            None => Ok(Complex::from_f64(s.parse()?))
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.re == 0.0 && self.0.im == 0.0
+    }
+
+    /// Shows numbers that are not real as `3.000 000 000 + 4.000 000 000i`.
+    fn format(&self) -> String {
+        match self.real() {
+            Some(re) => crate::format_num(&re),
+            None => format!("{} {} {}i",crate::format_num(&self.0.re),if self.0.im.is_sign_negative() { "-" } else { "+" },crate::format_num(&self.0.im.abs()).trim_start())
+        }
+    }
+
+    fn log10(self) -> Self { self.unary(f64::log10,Complex64::log10) }
+    fn log2(self) -> Self { self.unary(f64::log2,Complex64::log2) }
+    fn ln(self) -> Self { self.unary(f64::ln,Complex64::ln) }
+    fn sqrt(self) -> Self { self.unary(f64::sqrt,Complex64::sqrt) }
+    fn powf(self, exp: Self) -> Self { self.binary(exp,f64::powf,Complex64::powc) }
+    fn log(self, base: Self) -> Self { self.binary(base,f64::log,|n,base| n.ln() / base.ln()) }
+    fn recip(self) -> Self { self.unary(f64::recip,Complex64::finv) }
+    fn sin(self) -> Self { self.unary(f64::sin,Complex64::sin) }
+    fn cos(self) -> Self { self.unary(f64::cos,Complex64::cos) }
+    fn tan(self) -> Self { self.unary(f64::tan,Complex64::tan) }
+    fn asin(self) -> Self { self.unary(f64::asin,Complex64::asin) }
+    fn acos(self) -> Self { self.unary(f64::acos,Complex64::acos) }
+    fn atan(self) -> Self { self.unary(f64::atan,Complex64::atan) }
+
+    fn re(self) -> Self { Complex::from_f64(self.0.re) }
+    fn im(self) -> Self { Complex::from_f64(self.0.im) }
+    fn conj(self) -> Self { Complex(self.0.conj()) }
+    fn arg(self) -> Self { Complex::from_f64(self.0.arg()) }
+    fn abs(self) -> Self { Complex::from_f64(self.0.norm()) }
+    fn from_polar(r: Self, theta: Self) -> Self {
+        match (r.real(),theta.real()) {
+            (Some(r),Some(theta)) => Complex(Complex64::from_polar(r,theta)),
+            _ => Complex::from_f64(f64::NAN)
+        }
+    }
+}
+
+/// Real operands are computed as floats, so that an infinity times a real number has no NaN in
+/// its imaginary part.
+macro_rules! complex_ops {
+    ($($op:ident $method:ident),*) => {$(
+        impl $op for Complex {
+            type Output = Complex;
+            fn $method(self, rhs: Complex) -> Complex {
+                self.binary(rhs,f64::$method,Complex64::$method)
+            }
+        }
+    )*}
+}
+
+complex_ops!(Add add, Sub sub, Mul mul, Div div);
+
+/// Only real numbers are ordered.
+impl PartialOrd for Complex {
+    fn partial_cmp(&self, other: &Complex) -> Option<std::cmp::Ordering> {
+        self.real()?.partial_cmp(&other.real()?)
+    }
+}
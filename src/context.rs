@@ -0,0 +1,35 @@
+use std::collections::{BTreeMap,HashMap};
+use std::path::PathBuf;
+use std::rc::Rc;
+use crate::Chain;
+use crate::value::Value;
+use crate::number::Number;
+
+/// Interpreter state that outlives a single command, kept apart from the stack.
+#[derive(Clone)]
+pub struct Context<N = f64> {
+    /// Words created with `def`, keyed by their upper-cased name. Shared so calling a word
+    /// does not copy it, and so compiled code can be cached per definition.
+    pub words: HashMap<String,Rc<Chain>>,
+    /// Values stored with `sto`, keyed by their upper-cased name
+    pub registers: BTreeMap<String,Value<N>>,
+    /// How many word calls are currently nested
+    pub depth: usize,
+    /// Nesting of word calls past which a call fails instead of recursing further
+    pub max_depth: usize,
+    /// Canonical paths of the script being run and of every file it is currently including,
+    /// outermost first
+    pub files: Vec<PathBuf>
+}
+
+impl<N: Number> Default for Context<N> {
+    fn default() -> Self {
+        Context {
+            words: HashMap::new(),
+            registers: BTreeMap::new(),
+            depth: 0,
+            max_depth: crate::MAX_CALL_DEPTH,
+            files: vec![]
+        }
+    }
+}
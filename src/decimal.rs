@@ -0,0 +1,293 @@
+use std::ops::{Add,Sub,Mul,Div};
+use std::cell::Cell;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Zero,Signed,ToPrimitive};
+use crate::error::*;
+use crate::number::Number;
+use crate::rational::Rational;
+
+/// Largest exponent a decimal is raised to exactly by `pow` before falling back to floating
+/// point, as the digits of the result grow with it
+const MAX_EXACT_EXPONENT: u32 = 10_000;
+
+/// Largest scale the tui accepts, as every operation computes with numbers of that many digits
+pub const MAX_SCALE: u32 = 1_000;
+
+/// How results with more decimals than the scale are rounded.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Rounding {
+    /// To the nearest, and ties to an even last digit, which does not bias sums of many roundings
+    HalfEven,
+    /// To the nearest, and ties away from zero
+    HalfUp,
+    /// Towards zero
+    Truncate
+}
+
+/// Configuration of the decimal mode.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Settings {
+    /// Decimals kept after the point
+    pub scale: u32,
+    pub rounding: Rounding
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { scale: 2, rounding: Rounding::HalfEven }
+    }
+}
+
+thread_local! {
+    static SETTINGS: Cell<Settings> = Cell::new(Settings::default());
+}
+
+/// Sets the scale of decimals created on this thread from now on, and the rounding of the
+/// operations run on it. Decimals created before keep the scale they had.
+pub fn configure(settings: Settings) {
+    SETTINGS.with(|s| s.set(settings));
+}
+
+fn settings() -> Settings {
+    SETTINGS.with(Cell::get)
+}
+
+/// Number of the decimal mode: a fixed number of decimals, set by [`configure`], so that sums of
+/// amounts like `0.10` are exact, and every other result is rounded to it. Results of operations
+/// on decimals of different scales have the larger one.
+#[derive(Clone,Debug)]
+pub enum Decimal {
+    /// Integer count of the smallest decimal unit, like cents at a scale of 2, and the scale
+    Fixed(BigInt,u32),
+    /// Infinity or NaN, which have no decimal form
+    NonFinite(f64)
+}
+
+impl Decimal {
+    /// Value of the smallest decimal unit at a scale, as a number of them per one.
+    fn unit(scale: u32) -> BigInt {
+        num_traits::pow(BigInt::from(10),scale as usize)
+    }
+
+    /// Counts of the smallest unit of two decimals at the larger of their scales, and that scale.
+    fn aligned((lhs,lhs_scale): (&BigInt,u32), (rhs,rhs_scale): (&BigInt,u32)) -> (BigInt,BigInt,u32) {
+        let scale = lhs_scale.max(rhs_scale);
+        (lhs * Decimal::unit(scale - lhs_scale),rhs * Decimal::unit(scale - rhs_scale),scale)
+    }
+
+    /// Rounds a division of integers to an integer.
+    fn round_div(num: BigInt, den: &BigInt) -> BigInt {
+        let (quot,rem) = (&num / den,&num % den);
+        if rem.is_zero() {
+            return quot;
+        }
+        let away = if num.is_negative() == den.is_negative() { &quot + 1u32 } else { &quot - 1u32 };
+        let half = (rem.abs() * 2u32).cmp(&den.abs());
+        match (settings().rounding,half) {
+            (Rounding::Truncate,_) | (_,std::cmp::Ordering::Less) => quot,
+            (_,std::cmp::Ordering::Greater) | (Rounding::HalfUp,_) => away,
+            (Rounding::HalfEven,_) => if (&quot % 2u32).is_zero() { quot } else { away }
+        }
+    }
+
+    /// Rounds an exact number to a scale.
+    fn round(r: &BigRational, scale: u32) -> Decimal {
+        Decimal::Fixed(Decimal::round_div(r.numer() * Decimal::unit(scale),r.denom()),scale)
+    }
+
+    /// Rounds an exact number to the configured scale.
+    fn new(r: &BigRational) -> Decimal {
+        Decimal::round(r,settings().scale)
+    }
+
+    fn ratio(&self) -> Option<BigRational> {
+        match self {
+            Decimal::Fixed(n,scale) => Some(BigRational::new(n.clone(),Decimal::unit(*scale))),
+            Decimal::NonFinite(_) => None
+        }
+    }
+}
+
+impl Number for Decimal {
+    /// Rounds the exact value of the float to the scale.
+    fn from_f64(n: f64) -> Self {
+        match BigRational::from_float(n) {
+            Some(r) => Decimal::new(&r),
+            None => Decimal::NonFinite(n)
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        match self {
+            Decimal::Fixed(_,_) => self.ratio().and_then(|r| r.to_f64()).unwrap_or(f64::NAN),
+            Decimal::NonFinite(n) => *n
+        }
+    }
+
+    fn from_literal(n: f64) -> Self {
+        match Rational::decimal(&n.to_string()) {
+            Some(r) if n.is_finite() => Decimal::new(&r),
+            _ => Decimal::NonFinite(n)
+        }
+    }
+
+    fn from_ratio(num: f64, den: f64) -> Self {
+        match (BigRational::from_float(num),BigRational::from_float(den)) {
+            (Some(num),Some(den)) if !den.is_zero() => Decimal::new(&(num / den)),
+            _ => Decimal::NonFinite(num / den)
+        }
+    }
+
+    fn from_source(text: &str) -> Self {
+        match Rational::decimal(text) {
+            Some(r) => Decimal::new(&r),
+            None => Decimal::from_f64(text.parse().unwrap_or(f64::NAN))
+        }
+    }
+
+    fn from_ratio_source(num: &str, den: &str) -> Self {
+        match (num.parse::<BigInt>(),den.parse::<BigInt>()) {
+            (Ok(num),Ok(den)) if !den.is_zero() => Decimal::new(&BigRational::new(num,den)),
+            _ => Decimal::from_ratio(num.parse().unwrap_or(f64::NAN),den.parse().unwrap_or(f64::NAN))
+        }
+    }
+
+    /// Only decimals at the configured scale are literals, as those are read at it.
+    fn to_literal(&self) -> Option<f64> {
+        let n = self.to_f64();
+        match (self,Decimal::from_literal(n)) {
+            (Decimal::Fixed(lhs,lhs_scale),Decimal::Fixed(rhs,rhs_scale)) => (*lhs == rhs && *lhs_scale == rhs_scale).then_some(n),
+            _ => None
+        }
+    }
+
+    /// Numbers with exponents too large to read exactly are rounded from floating point.
+    fn parse(s: &str) -> Result<Self> {
+        let n = s.parse::<f64>()?;
+        Ok(match Rational::decimal(s) {
+            Some(r) if n.is_finite() => Decimal::new(&r),
+            _ => Decimal::from_f64(n)
+        })
+    }
+
+    fn is_zero(&self) -> bool {
+        match self {
+            Decimal::Fixed(n,_) => n.is_zero(),
+            Decimal::NonFinite(_) => false
+        }
+    }
+
+    /// Shows exactly as many decimals as the scale.
+    fn format(&self) -> String {
+        let (n,scale) = match self {
+            Decimal::Fixed(n,scale) => (n,*scale as usize),
+            Decimal::NonFinite(n) => return crate::format_num(n)
+        };
+        let digits = n.abs().to_string();
+        let digits = "0".repeat((scale + 1).saturating_sub(digits.len())) + &digits;
+        let (int,frac) = digits.split_at(digits.len() - scale);
+        let sign = if n.is_negative() { "-" } else { " " };
+        match frac {
+            "" => format!("{sign}{}",crate::group_digits(int)),
+            frac => format!("{sign}{}",crate::group_digits(&format!("{int}.{frac}")))
+        }
+    }
+
+    /// Keeps the scale of the base.
+    fn powf(self, exp: Self) -> Self {
+        if let (Decimal::Fixed(_,scale),Some(base),Some(e)) = (&self,self.ratio(),exp.ratio()) {
+            if let Some(e) = e.is_integer().then(|| e.to_integer().to_i32()).flatten().filter(|e| e.unsigned_abs() <= MAX_EXACT_EXPONENT) {
+                if e >= 0 || !base.is_zero() {
+                    return Decimal::round(&num_traits::Pow::pow(&base,e),*scale);
+                }
+            }
+        }
+        Decimal::from_f64(self.to_f64().powf(exp.to_f64()))
+    }
+
+    fn recip(self) -> Self {
+        match self {
+            Decimal::Fixed(_,scale) => Decimal::Fixed(Decimal::unit(scale),scale) / self,
+            n => Decimal::from_f64(n.to_f64().recip())
+        }
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+    fn add(self, rhs: Decimal) -> Decimal {
+        match (self,rhs) {
+            (Decimal::Fixed(lhs,lhs_scale),Decimal::Fixed(rhs,rhs_scale)) => {
+                let (lhs,rhs,scale) = Decimal::aligned((&lhs,lhs_scale),(&rhs,rhs_scale));
+                Decimal::Fixed(lhs + rhs,scale)
+            },
+            (lhs,rhs) => Decimal::from_f64(lhs.to_f64() + rhs.to_f64())
+        }
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+    fn sub(self, rhs: Decimal) -> Decimal {
+        match (self,rhs) {
+            (Decimal::Fixed(lhs,lhs_scale),Decimal::Fixed(rhs,rhs_scale)) => {
+                let (lhs,rhs,scale) = Decimal::aligned((&lhs,lhs_scale),(&rhs,rhs_scale));
+                Decimal::Fixed(lhs - rhs,scale)
+            },
+            (lhs,rhs) => Decimal::from_f64(lhs.to_f64() - rhs.to_f64())
+        }
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+    /// Rounds the product to the scale.
+    fn mul(self, rhs: Decimal) -> Decimal {
+        match (self,rhs) {
+            (Decimal::Fixed(lhs,lhs_scale),Decimal::Fixed(rhs,rhs_scale)) => {
+                Decimal::Fixed(Decimal::round_div(lhs * rhs,&Decimal::unit(lhs_scale.min(rhs_scale))),lhs_scale.max(rhs_scale))
+            },
+            (lhs,rhs) => Decimal::from_f64(lhs.to_f64() * rhs.to_f64())
+        }
+    }
+}
+
+impl Div for Decimal {
+    type Output = Decimal;
+    /// Rounds the quotient to the scale. Dividing by zero gives an infinity or NaN, as it does with
+    /// floats.
+    fn div(self, rhs: Decimal) -> Decimal {
+        match (self,rhs) {
+            (Decimal::Fixed(lhs,lhs_scale),Decimal::Fixed(rhs,rhs_scale)) if !rhs.is_zero() => {
+                let scale = lhs_scale.max(rhs_scale);
+                Decimal::Fixed(Decimal::round_div(lhs * Decimal::unit(rhs_scale + scale),&(rhs * Decimal::unit(lhs_scale))),scale)
+            },
+            (lhs,rhs) => Decimal::from_f64(lhs.to_f64() / rhs.to_f64())
+        }
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Decimal) -> bool {
+        match (self,other) {
+            (Decimal::Fixed(lhs,lhs_scale),Decimal::Fixed(rhs,rhs_scale)) => {
+                let (lhs,rhs,_) = Decimal::aligned((lhs,*lhs_scale),(rhs,*rhs_scale));
+                lhs == rhs
+            },
+            (lhs,rhs) => lhs.to_f64() == rhs.to_f64()
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<std::cmp::Ordering> {
+        match (self,other) {
+            (Decimal::Fixed(lhs,lhs_scale),Decimal::Fixed(rhs,rhs_scale)) => {
+                let (lhs,rhs,_) = Decimal::aligned((lhs,*lhs_scale),(rhs,*rhs_scale));
+                lhs.partial_cmp(&rhs)
+            },
+            (lhs,rhs) => lhs.to_f64().partial_cmp(&rhs.to_f64())
+        }
+    }
+}
@@ -1,27 +1,111 @@
-
 #[derive(Debug,thiserror::Error)]
 pub enum Error {
-    #[error("Failed to parse as any component of an enum: \n{0:?}")]
-    ParseEnum(Vec<Error>),
 	#[error("Failed to parse float: \n{0}")]
 	ParseFloat(#[from] std::num::ParseFloatError),
-    #[error("Failed to parse integer: \n{0}")]
-    ParseInt(#[from] std::num::ParseIntError),
     #[error("Not enough elements in stack to run input.\n Elements in stack ({0}) < Elements needed ({1})")]
     StackEmpty(usize,usize),
-    #[error("Could not parse token: {0}")]
-    ParseToken(&'static str),
+    #[error("Expected a {0} on the stack, found a {1}")]
+    Type(&'static str,&'static str),
+    #[error("Expected {0}, found {1}")]
+    Expected(&'static str,String),
+    #[error("Complex numbers are only available in complex mode")]
+    Complex,
+    #[error("Numbers with a radius are only available in interval and uncertainty modes")]
+    PlusMinus,
+    #[error("No such date: {0}, its month or day is not on the calendar")]
+    DateRange(String),
+    #[error("Incompatible units: {0} and {1}")]
+    Units(String,String),
+    #[error("Unknown unit: {0}")]
+    UnknownUnit(String),
+    #[error("Expected {0}, found an array of shape {1}")]
+    Shape(String,String),
+    #[error("Matrix is singular")]
+    Singular,
+    #[error("Unknown command or operation: {0}")]
+    UnknownWord(String),
+    #[error("Invalid arguments, expected: {0}")]
+    BadArgs(&'static str),
+    #[error("Unknown escape sequence in string: \\{0}")]
+    Escape(char),
+    #[error("Invalid unicode escape in string, expected \\u{{X}} with one to six hex digits of a valid character")]
+    UnicodeEscape,
+    #[error("Invalid placeholder in string: {0}")]
+    Placeholder(String),
+    #[error("String is missing its closing quote")]
+    UnterminatedString,
+    #[error("Block comment is missing its closing */")]
+    UnterminatedComment,
     #[error("Command tried to access the stack out of bounds, Index {0} is not within the stack sized {1}")]
     OOB(usize,usize),
     #[error("Possible infinite loop detected. Repetition reached maximum limit")]
     InfLoop,
+    #[error("Word calls nested deeper than the limit of {0}")]
+    RecursionLimit(usize),
+    #[error("No register named {0}")]
+    UnknownRegister(String),
+    #[error("Cannot define a word named {0}, it is already a built-in command or operation")]
+    Redefine(String),
+    #[error("Include cycle: {0}")]
+    IncludeCycle(String),
+    #[error("Not enough values on the stack: this needs {0}, but there may be only {1}")]
+    Underflow(usize,usize),
+    #[error("Repeating this {0} times takes more values than the {1} on the stack")]
+    LoopUnderflow(usize,usize),
+    #[error("The branches of this if change the stack depth differently, by {0} and by {1}")]
+    BranchDepths(isize,isize),
+    #[error("{1}\n In word {0}, called here")]
+    InWord(String,Box<Error>),
     #[error("Unbalanced Braces")]
     UnbBraces,
     #[error("Break command was run")]
     Break,
     #[error("Error during IO: \n{0}")]
-    IO(#[from] std::io::Error)
+    IO(#[from] std::io::Error),
+    #[error("{2}\n In loop iteration {0}, with index {1}")]
+    Iteration(usize,f64,Box<Error>),
+    #[error("{1}\n In {0}, included from")]
+    Included(String,Box<Error>),
+    #[error("{1}")]
+    At(crate::span::Span,Box<Error>),
+    #[error("{0}")]
+    Diagnostic(Box<crate::span::Diagnostic>)
 }
 
-pub type Result<T=(), E=Error> = std::result::Result<T,E>;
+impl Error {
+    /// Attaches a location to the error, unless it already has a more precise one.
+    pub fn at(self, span: crate::span::Span) -> Error {
+        match self {
+            Error::At(_,_) | Error::Diagnostic(_) => self,
+            e => Error::At(span,Box::new(e))
+        }
+    }
+
+    /// Records the loop iteration the error happened in, keeping any location innermost.
+    pub fn in_iteration(self, iteration: usize, index: f64) -> Error {
+        match self {
+            Error::At(span,e) => Error::At(span,Box::new(e.in_iteration(iteration,index))),
+            e => Error::Iteration(iteration,index,Box::new(e))
+        }
+    }
+
+    /// The error without any location or iteration attached.
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::At(_,e) | Error::Iteration(_,_,e) | Error::Included(_,e) | Error::InWord(_,e) => e.inner(),
+            Error::Diagnostic(d) => d.error.inner(),
+            e => e
+        }
+    }
 
+    /// Resolves the attached location against the source it came from, so the error displays
+    /// its file, line and column along with the offending line.
+    pub fn with_source(self, file: &str, source: &str) -> Error {
+        match self {
+            Error::At(span,e) => Error::Diagnostic(Box::new(crate::span::Diagnostic::new(file,source,span,*e))),
+            e => e
+        }
+    }
+}
+
+pub type Result<T=(), E=Error> = std::result::Result<T,E>;
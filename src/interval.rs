@@ -0,0 +1,344 @@
+use std::ops::{Add,Sub,Mul,Div};
+use std::f64::consts::{PI,FRAC_PI_2};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Signed;
+use crate::error::*;
+use crate::number::Number;
+use crate::rational::Rational;
+
+/// Ulps by which results of the math library are widened on each side. Its functions are not
+/// correctly rounded, but stay within an ulp or two of the exact result on common platforms.
+const LIBM_ULPS: usize = 2;
+
+/// Magnitude above which the multiples of pi are rounded too coarsely to tell where the peaks
+/// and poles of the trigonometric functions are, so that they are assumed to be anywhere.
+const MAX_PERIODIC: f64 = 1e6;
+
+/// Number of the interval mode: bounds that the exact result is guaranteed to lie within.
+/// Every operation rounds the lower bound down and the upper bound up, so that the width of the
+/// interval shows how much error a computation has accumulated.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64
+}
+
+impl Interval {
+    fn point(n: f64) -> Interval {
+        Interval { lo: n, hi: n }
+    }
+
+    fn entire() -> Interval {
+        Interval { lo: f64::NEG_INFINITY, hi: f64::INFINITY }
+    }
+
+    fn nan() -> Interval {
+        Interval::point(f64::NAN)
+    }
+
+    fn is_nan(&self) -> bool {
+        self.lo.is_nan() || self.hi.is_nan()
+    }
+
+    fn contains(&self, n: f64) -> bool {
+        self.lo <= n && n <= self.hi
+    }
+
+    /// The part of the interval inside the domain of a function, or NaN when there is none.
+    fn within(self, min: f64, max: f64) -> Interval {
+        let (lo,hi) = (self.lo.max(min),self.hi.min(max));
+        if lo <= hi && !self.is_nan() { Interval { lo, hi } } else { Interval::nan() }
+    }
+
+    /// Applies a non-decreasing function of the math library.
+    fn increasing(self, f: fn(f64) -> f64) -> Interval {
+        Interval { lo: down(f(self.lo),LIBM_ULPS), hi: up(f(self.hi),LIBM_ULPS) }
+    }
+
+    /// Applies a non-increasing function of the math library.
+    fn decreasing(self, f: fn(f64) -> f64) -> Interval {
+        Interval { lo: down(f(self.hi),LIBM_ULPS), hi: up(f(self.lo),LIBM_ULPS) }
+    }
+
+    /// Applies sine or cosine, which peak at `peak` and bottom out pi later, every 2 pi.
+    fn periodic(self, f: fn(f64) -> f64, peak: f64) -> Interval {
+        if self.is_nan() {
+            return Interval::nan();
+        }
+        let (a,b) = (f(self.lo),f(self.hi));
+        Interval {
+            lo: if crosses(self,peak + PI,2.0 * PI) { -1.0 } else { down(a.min(b),LIBM_ULPS).max(-1.0) },
+            hi: if crosses(self,peak,2.0 * PI) { 1.0 } else { up(a.max(b),LIBM_ULPS).min(1.0) }
+        }
+    }
+}
+
+/// Whether `at` plus a multiple of `period` may lie in the interval. Close calls count as
+/// crossings, as the multiples of pi are rounded.
+fn crosses(x: Interval, at: f64, period: f64) -> bool {
+    const SLACK: f64 = 1e-9;
+    if x.lo.abs().max(x.hi.abs()) > MAX_PERIODIC {
+        return true;
+    }
+    ((x.lo - at) / period - SLACK).ceil() <= ((x.hi - at) / period + SLACK).floor()
+}
+
+fn down(x: f64, ulps: usize) -> f64 {
+    (0..ulps).fold(x,|x,_| x.next_down())
+}
+
+fn up(x: f64, ulps: usize) -> f64 {
+    (0..ulps).fold(x,|x,_| x.next_up())
+}
+
+/// Bounds of an exact result from its rounding to nearest `x` and the error `err` of that
+/// rounding. An infinite `x` without a known error is taken as an overflow of a finite result.
+fn bounds(x: f64, err: f64) -> (f64,f64) {
+    if x.is_infinite() && err.is_nan() {
+        return if x > 0.0 { (f64::MAX,x) } else { (x,f64::MIN) };
+    }
+    (if err < 0.0 { x.next_down() } else { x },if err > 0.0 { x.next_up() } else { x })
+}
+
+/// Like [`bounds`], for results whose error is computed with a fused multiply-add, which loses
+/// it when the result is close to underflowing.
+fn fma_bounds(x: f64, err: f64) -> (f64,f64) {
+    if x.abs() < f64::MIN_POSITIVE * 2f64.powi(f64::MANTISSA_DIGITS as i32) {
+        (x.next_down(),x.next_up())
+    } else {
+        bounds(x,err)
+    }
+}
+
+fn sum(a: f64, b: f64) -> (f64,f64) {
+    let s = a + b;
+    let bb = s - a;
+    bounds(s,(a - (s - bb)) + (b - bb))
+}
+
+fn product(a: f64, b: f64) -> (f64,f64) {
+    if a == 0.0 || b == 0.0 {
+        return (0.0,0.0);
+    }
+    let p = a * b;
+    fma_bounds(p,a.mul_add(b,-p))
+}
+
+fn quotient(a: f64, b: f64) -> (f64,f64) {
+    let q = a / b;
+    let rem = -q.mul_add(b,-a);
+    fma_bounds(q,if b < 0.0 { -rem } else { rem })
+}
+
+/// Hull of an operation on every pair of bounds, which holds the result of operations that are
+/// monotonic in each of their inputs.
+fn corners(a: Interval, b: Interval, f: fn(f64,f64) -> (f64,f64)) -> Interval {
+    if a.is_nan() || b.is_nan() {
+        return Interval::nan();
+    }
+    let corners = [f(a.lo,b.lo),f(a.lo,b.hi),f(a.hi,b.lo),f(a.hi,b.hi)];
+    Interval {
+        lo: corners.iter().map(|c| c.0).fold(f64::INFINITY,f64::min),
+        hi: corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY,f64::max)
+    }
+}
+
+/// Hull of results of the math library, widened like those of [`Interval::increasing`].
+fn hull(values: impl Iterator<Item = f64>) -> Interval {
+    let (lo,hi) = values.fold((f64::INFINITY,f64::NEG_INFINITY),|(lo,hi),x| (lo.min(x),hi.max(x)));
+    if lo > hi { Interval::nan() } else { Interval { lo: down(lo,LIBM_ULPS), hi: up(hi,LIBM_ULPS) } }
+}
+
+/// Writes a bound with as many decimals as `format_num`, rounded away from the inside of the
+/// interval so that the written bound still holds.
+fn bound(x: f64, round_up: bool) -> String {
+    let Some(exact) = BigRational::from_float(x) else { return crate::format_num(&x).trim_start().to_string() };
+    let scaled = exact * BigRational::from_integer(num_traits::pow(BigInt::from(10),9));
+    let n = if round_up { scaled.ceil() } else { scaled.floor() }.to_integer();
+    let digits = format!("{:0>10}",n.abs());
+    let (int,frac) = digits.split_at(digits.len() - 9);
+    format!("{}{}",if n.is_negative() { "-" } else { "" },crate::group_digits(&format!("{int}.{frac}")).trim_start())
+}
+
+impl Number for Interval {
+    fn from_f64(n: f64) -> Self {
+        Interval::point(n)
+    }
+
+    /// The midpoint.
+    fn to_f64(&self) -> f64 {
+        if self.lo == self.hi { self.lo } else { self.lo / 2.0 + self.hi / 2.0 }
+    }
+
+    /// Literals like `0.1` that floats cannot hold exactly are widened to the floats around them.
+    fn from_literal(n: f64) -> Self {
+        match (Rational::decimal(&n.to_string()),BigRational::from_float(n)) {
+            (Some(decimal),Some(float)) if decimal != float => Interval { lo: n.next_down(), hi: n.next_up() },
+            _ => Interval::point(n)
+        }
+    }
+
+    fn from_ratio(num: f64, den: f64) -> Self {
+        Interval::from_literal(num) / Interval::from_literal(den)
+    }
+
+    /// Reads the text, so that literals with more digits than floats hold, like
+    /// `9007199254740993`, are widened too.
+    fn from_source(text: &str) -> Self {
+        let n = text.parse::<f64>().unwrap_or(f64::NAN);
+        let exact = match Rational::decimal(text) {
+            Some(decimal) => BigRational::from_float(n) == Some(decimal),
+            // Infinity and NaN, unless the exponent is too large to read exactly
+            None => !text.contains(|c: char| c.is_ascii_digit())
+        };
+        if exact { Interval::point(n) } else { Interval { lo: n.next_down(), hi: n.next_up() } }
+    }
+
+    fn from_ratio_source(num: &str, den: &str) -> Self {
+        Interval::from_source(num) / Interval::from_source(den)
+    }
+
+    fn from_plus_minus(mid: f64, radius: f64) -> Option<Self> {
+        let (mid,radius) = (Interval::from_literal(mid),Interval::from_literal(radius.abs()));
+        Some(Interval { lo: (mid - radius).lo, hi: (mid + radius).hi })
+    }
+
+    /// Only intervals holding a single number are literals.
+    fn to_literal(&self) -> Option<f64> {
+        (Interval::from_literal(self.lo) == *self).then_some(self.lo)
+    }
+
+    /// Reads numbers, and ranges of them written as `1.2±0.05`.
+    fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match crate::lexer::plus_minus_literal(s) {
+            Some((mid,radius)) => Interval::from_plus_minus(mid,radius).ok_or(Error::PlusMinus),
+            None => {
+                s.parse::<f64>()?;
+                Ok(Interval::from_source(s))
+            }
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.lo == 0.0 && self.hi == 0.0
+    }
+
+    /// Shows the bounds as `[1.150 000 000, 1.250 000 000]`, or a single number when they are
+    /// the same.
+    fn format(&self) -> String {
+        if self.lo == self.hi || self.is_nan() {
+            crate::format_num(&self.lo)
+        } else {
+            format!(" [{}, {}]",bound(self.lo,false),bound(self.hi,true))
+        }
+    }
+
+    fn log10(self) -> Self { self.within(0.0,f64::INFINITY).increasing(f64::log10) }
+    fn log2(self) -> Self { self.within(0.0,f64::INFINITY).increasing(f64::log2) }
+    fn ln(self) -> Self { self.within(0.0,f64::INFINITY).increasing(f64::ln) }
+
+    /// Square roots are correctly rounded, so only need widening when they are inexact.
+    fn sqrt(self) -> Self {
+        let x = self.within(0.0,f64::INFINITY);
+        let root = |x: f64| {
+            let s = x.sqrt();
+            fma_bounds(s,-s.mul_add(s,-x))
+        };
+        if x.is_nan() { x } else { Interval { lo: root(x.lo).0, hi: root(x.hi).1 } }
+    }
+
+    /// Powers of negative numbers are only defined for integer exponents. Any other exponent
+    /// only takes the non-negative part of the base.
+    fn powf(self, exp: Self) -> Self {
+        if self.is_nan() || exp.is_nan() {
+            return Interval::nan();
+        }
+        if exp.lo == exp.hi && exp.lo.fract() == 0.0 {
+            let n = exp.lo;
+            if n < 0.0 && self.contains(0.0) {
+                return Interval::entire();
+            }
+            let zero = self.contains(0.0).then(|| 0f64.powf(n));
+            return hull([self.lo.powf(n),self.hi.powf(n)].into_iter().chain(zero));
+        }
+        let base = self.within(0.0,f64::INFINITY);
+        if base.is_nan() {
+            return base;
+        }
+        hull([(base.lo,exp.lo),(base.lo,exp.hi),(base.hi,exp.lo),(base.hi,exp.hi)].into_iter().map(|(b,e)| b.powf(e)))
+    }
+
+    fn log(self, base: Self) -> Self { self.ln() / base.ln() }
+    fn recip(self) -> Self { Interval::point(1.0) / self }
+    fn sin(self) -> Self { self.periodic(f64::sin,FRAC_PI_2) }
+    fn cos(self) -> Self { self.periodic(f64::cos,0.0) }
+
+    fn tan(self) -> Self {
+        if self.is_nan() || crosses(self,FRAC_PI_2,PI) { Interval::entire() } else { self.increasing(f64::tan) }
+    }
+
+    fn asin(self) -> Self { self.within(-1.0,1.0).increasing(f64::asin) }
+    fn acos(self) -> Self { self.within(-1.0,1.0).decreasing(f64::acos) }
+    fn atan(self) -> Self { self.increasing(f64::atan) }
+
+    fn abs(self) -> Self {
+        if self.lo >= 0.0 {
+            self
+        } else if self.hi <= 0.0 {
+            Interval { lo: -self.hi, hi: -self.lo }
+        } else {
+            Interval { lo: 0.0, hi: self.hi.max(-self.lo) }
+        }
+    }
+}
+
+impl Add for Interval {
+    type Output = Interval;
+    fn add(self, rhs: Interval) -> Interval {
+        Interval { lo: sum(self.lo,rhs.lo).0, hi: sum(self.hi,rhs.hi).1 }
+    }
+}
+
+impl Sub for Interval {
+    type Output = Interval;
+    fn sub(self, rhs: Interval) -> Interval {
+        Interval { lo: sum(self.lo,-rhs.hi).0, hi: sum(self.hi,-rhs.lo).1 }
+    }
+}
+
+impl Mul for Interval {
+    type Output = Interval;
+    fn mul(self, rhs: Interval) -> Interval {
+        corners(self,rhs,product)
+    }
+}
+
+impl Div for Interval {
+    type Output = Interval;
+    /// Dividing by an interval holding zero can give any number.
+    fn div(self, rhs: Interval) -> Interval {
+        if !self.is_nan() && rhs.contains(0.0) {
+            Interval::entire()
+        } else {
+            corners(self,rhs,quotient)
+        }
+    }
+}
+
+/// An interval is less than another when all of its numbers are, so that overlapping intervals
+/// are not ordered.
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Interval) -> Option<std::cmp::Ordering> {
+        if self.hi < other.lo {
+            Some(std::cmp::Ordering::Less)
+        } else if self.lo > other.hi {
+            Some(std::cmp::Ordering::Greater)
+        } else if self.lo == self.hi && self == other {
+            Some(std::cmp::Ordering::Equal)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::error::*;
+use crate::span::Span;
+use crate::ast::Comment;
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum Token {
+    /// Value and text of a number, as in `1.5`, whose text exact backends read every digit of
+    Num(f64,String),
+    /// Numerator and denominator of a fraction of two integers as written, as in `1/3`
+    Frac(String,String),
+    /// Real and imaginary parts of a complex number, as in `3+4i`
+    Complex(f64,f64),
+    /// Midpoint and radius of a range of numbers, as in `1.2±0.05`
+    PlusMinus(f64,f64),
+    /// Date, time of day or duration, as in `2026-10-17`, `14:30` or `1h30m`
+    Time(crate::time::Literal),
+    Word(String),
+    Str(String),
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Comma,
+    Semi,
+    Comment(Comment)
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Num(_,text) => write!(f,"{text}"),
+            Token::Frac(n,d) => write!(f,"{n}/{d}"),
+            Token::Complex(re,im) => write!(f,"{}",complex_source(*re,*im)),
+            Token::PlusMinus(mid,radius) => write!(f,"{}",plus_minus_source(*mid,*radius)),
+            Token::Time(time) => write!(f,"{time}"),
+            Token::Word(w) => write!(f,"{w}"),
+            Token::Str(s) => write!(f,"{s:?}"),
+            Token::LBracket => write!(f,"["),
+            Token::RBracket => write!(f,"]"),
+            Token::LParen => write!(f,"("),
+            Token::RParen => write!(f,")"),
+            Token::Comma => write!(f,","),
+            Token::Semi => write!(f,";"),
+            Token::Comment(c) => write!(f,"{c}")
+        }
+    }
+}
+
+/// Writes `s` as a string literal that reads back as the same string.
+pub fn quote_str(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}",c as u32)),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Writes `n` as a number literal that reads back as the same number, switching to an exponent
+/// for very large or small magnitudes.
+pub fn num_source(n: f64) -> String {
+    if n != 0.0 && (n.abs() >= 1e16 || n.abs() < 1e-4) {
+        format!("{n:e}")
+    } else {
+        format!("{n}")
+    }
+}
+
+/// Writes a number literal in its shortest form, as [`num_source`] does, unless that form has a
+/// different value because the literal has more digits than a float holds.
+pub fn literal_source(text: &str) -> String {
+    let short = text.parse().map(num_source).unwrap_or_default();
+    match (crate::rational::Rational::decimal(text),crate::rational::Rational::decimal(&short)) {
+        (Some(exact),Some(rounded)) if exact == rounded => short,
+        _ => text.to_string()
+    }
+}
+
+/// Writes a complex number as a literal like `3+4i` that reads back as the same number.
+pub fn complex_source(re: f64, im: f64) -> String {
+    if re == 0.0 {
+        format!("{}i",num_source(im))
+    } else if im.is_sign_negative() {
+        format!("{}{}i",num_source(re),num_source(im))
+    } else {
+        format!("{}+{}i",num_source(re),num_source(im))
+    }
+}
+
+/// Reads a complex number written as `3+4i`, `1.5e-3-2i` or `4i` into its real and imaginary
+/// parts. The imaginary part needs digits, so that a lone `i` stays a word.
+pub fn complex_literal(s: &str) -> Option<(f64,f64)> {
+    let s = s.strip_suffix('i').filter(|s| s.ends_with(|c: char| c.is_ascii_digit() || c == '.'))?;
+    let split = s.char_indices().skip(1)
+        .filter(|&(i,c)| matches!(c, '+' | '-') && !s[..i].ends_with(['e','E']))
+        .last();
+    match split {
+        Some((i,_)) => Some((s[..i].parse().ok()?,s[i..].parse().ok()?)),
+        None => Some((0.0,s.parse().ok()?))
+    }
+}
+
+/// Writes a number with its radius as a literal like `1.2±0.05`.
+pub fn plus_minus_source(mid: f64, radius: f64) -> String {
+    format!("{}±{}",num_source(mid),num_source(radius))
+}
+
+/// Reads a number with its radius written as `1.2±0.05`, or `1.2+-0.05` where `±` is hard to
+/// type.
+pub fn plus_minus_literal(s: &str) -> Option<(f64,f64)> {
+    let (mid,radius) = s.split_once('±').or_else(|| s.split_once("+-"))?;
+    Some((mid.parse().ok()?,radius.parse().ok()?))
+}
+
+/// Splits source text into tokens. Anything that is not whitespace, a delimiter, a comment or a
+/// string literal is read as a word, unless it is one of these literals:
+///
+/// - numbers, like `1.5`
+/// - fractions, like `2/3`
+/// - complex numbers, like `3+4i`
+/// - numbers with a radius, like `1.2±0.05`
+/// - dates, times of day and durations, like `2026-10-17`, `14:30` and `1h30m`
+pub struct Lexer<'a> {
+    input: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer { input, chars: input.char_indices().peekable() }
+    }
+
+    /// Byte offset of the next character.
+    fn pos(&mut self) -> usize {
+        self.chars.peek().map(|(i,_)| *i).unwrap_or(self.input.len())
+    }
+
+    fn is_delimiter(c: char) -> bool {
+        c.is_whitespace() || matches!(c, '[' | ']' | '(' | ')' | ',' | ';' | '"' | '#')
+    }
+
+    fn line_comment(&mut self, start: usize) -> Token {
+        let mut text = String::new();
+        while let Some((_,c)) = self.chars.next_if(|(_,c)| *c != '\n') {
+            text.push(c);
+        }
+        Token::Comment(Comment::Line(text,Span::new(start,self.pos())))
+    }
+
+    fn block_comment(&mut self, start: usize) -> Result<Token> {
+        match self.input[start + 2..].find("*/") {
+            Some(len) => {
+                let text = self.input[start + 2..start + 2 + len].to_string();
+                let end = start + 2 + len + 2;
+                while self.chars.next_if(|(i,_)| *i < end).is_some() {}
+                Ok(Token::Comment(Comment::Block(text,Span::new(start,end))))
+            },
+            None => Err(Error::UnterminatedComment.at(Span::new(start,start + 2)))
+        }
+    }
+
+    fn string(&mut self, start: usize) -> Result<Token> {
+        let mut out = String::new();
+        loop {
+            let escape_start = self.pos();
+            match self.chars.next().map(|(_,c)| c) {
+                Some('"') => break Ok(Token::Str(out)),
+                Some('\\') => match self.chars.next().map(|(_,c)| c) {
+                    Some('\\') => out.push('\\'),
+                    Some('"') => out.push('"'),
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => match self.unicode_escape() {
+                        Some(c) => out.push(c),
+                        None => break Err(Error::UnicodeEscape.at(Span::new(escape_start,self.pos())))
+                    },
+                    Some(c) => break Err(Error::Escape(c).at(Span::new(escape_start,self.pos()))),
+                    None => break Err(Error::UnterminatedString.at(Span::new(start,self.pos())))
+                },
+                Some(c) => out.push(c),
+                None => break Err(Error::UnterminatedString.at(Span::new(start,self.pos())))
+            }
+        }
+    }
+
+    /// Reads the `{XXXX}` following `\u`, holding one to six hex digits.
+    fn unicode_escape(&mut self) -> Option<char> {
+        self.chars.next_if(|(_,c)| *c == '{')?;
+        let mut digits = String::new();
+        while let Some((_,c)) = self.chars.next_if(|(_,c)| c.is_ascii_hexdigit()) {
+            digits.push(c);
+        }
+        self.chars.next_if(|(_,c)| *c == '}')?;
+        if digits.is_empty() || digits.len() > 6 { return None }
+        char::from_u32(u32::from_str_radix(&digits,16).ok()?)
+    }
+
+    fn word(&mut self) -> Result<Token> {
+        let mut out = String::new();
+        while let Some(&(_,c)) = self.chars.peek() {
+            if Self::is_delimiter(c) { break }
+            out.push(c);
+            self.chars.next();
+        }
+        if let Ok(n) = out.parse::<f64>() {
+            return Ok(Token::Num(n,out));
+        }
+        if let Some((re,im)) = complex_literal(&out) {
+            return Ok(Token::Complex(re,im));
+        }
+        if let Some((mid,radius)) = plus_minus_literal(&out) {
+            return Ok(Token::PlusMinus(mid,radius));
+        }
+        if let Some(time) = crate::time::Literal::parse(&out) {
+            return time.map(Token::Time);
+        }
+        let is_int = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+        Ok(match out.split_once('/') {
+            Some((num,den)) if is_int(num.strip_prefix('-').unwrap_or(num)) && is_int(den) => Token::Frac(num.to_string(),den.to_string()),
+            _ => Token::Word(out)
+        })
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<(Token,Span)>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.chars.next_if(|(_,c)| c.is_whitespace()).is_some() {}
+        let &(start,c) = self.chars.peek()?;
+        let token = match c {
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            ';' => Token::Semi,
+            '"' => {
+                self.chars.next();
+                return Some(self.string(start).map(|t| (t,Span::new(start,self.pos()))))
+            },
+            '#' => {
+                self.chars.next();
+                let token = self.line_comment(start);
+                return Some(Ok((token,Span::new(start,self.pos()))))
+            },
+            '/' if self.input[start..].starts_with("/*") => {
+                return Some(self.block_comment(start).map(|t| (t,Span::new(start,self.pos()))))
+            },
+            _ => {
+                let token = self.word();
+                let span = Span::new(start,self.pos());
+                return Some(token.map(|t| (t,span)).map_err(|e| e.at(span)))
+            }
+        };
+        self.chars.next();
+        Some(Ok((token,Span::new(start,self.pos()))))
+    }
+}
@@ -6,8 +6,34 @@ use crate::error::*;
 use std::str::FromStr;
 
 pub mod ops;
+pub mod lexer;
+pub mod ast;
+pub mod parser;
+pub mod span;
+use crate::span::Span;
+pub mod context;
+use crate::context::Context;
+pub mod value;
+use crate::value::*;
+pub mod vm;
+pub mod check;
+pub mod optimize;
+pub mod number;
+pub mod rational;
+pub mod big;
+pub mod complex;
+pub mod decimal;
+pub mod interval;
+pub mod uncertainty;
+pub mod units;
+pub mod array;
+pub mod time;
+use crate::number::Number;
 
 const MAX_REPETITIONS: usize = 1_000_000;
+const MAX_CALL_DEPTH: usize = 1_000;
+/// Longest chain, in characters, that is printed between brackets on a single line
+const MAX_INLINE_BLOCK: usize = 60;
 
 pub trait CommandDesc {
     const SHORT_NAME: Option<&'static str>;
@@ -16,10 +42,19 @@ pub trait CommandDesc {
 }
 
 pub trait Command where
-    Self: std::str::FromStr + Clone,
-    Self::Err: Into<Error>
+    Self: Clone
 {
-	fn comm(self, stack: &mut Vec<f64>, stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>>;
+	fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>>;
+}
+
+/// Builds a command out of a parsed statement.
+pub trait Parse where
+    Self: Sized
+{
+    /// Upper-cased leading words that select this command. Empty for statements recognized by
+    /// their first atom instead, like numbers and blocks.
+    const KEYWORDS: &'static [&'static str];
+    fn parse(stmt: &ast::Stmt) -> Result<Self>;
 }
 
 macro_rules! command_enum {
@@ -29,36 +64,45 @@ macro_rules! command_enum {
         }
     } => {
         #[derive(Clone)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
         pub enum CommandEnum {
             $($v($v),)*
         }
 
         impl Command for CommandEnum {
             #[allow(unused_variables)]
-            fn comm(self, stack: &mut Vec<f64>, stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>> {
+            fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>> {
                 match self {
-                    $(CommandEnum::$v(v) => Command::comm(v,stack,stdin,stdout),)*
+                    $(CommandEnum::$v(v) => Command::comm(v,stack,ctx,stdin,stdout),)*
                 }
             }
         }
 
-        impl FromStr for CommandEnum {
-            type Err = Error;
-            #[allow(unused_variables,unused_mut)]
-            fn from_str(input: &str) -> Result<CommandEnum> {
-				let mut parse_errors: Vec<Error> = vec![];
-				let trimmed_input = input.trim();
-				$(
-					match trimmed_input.parse::<$v>() {
-						Err(e) => parse_errors.push(e.into()),
-						Ok(v) => return Ok(CommandEnum::$v(v))
-					}
-				)*
-				Err(Error::ParseEnum(parse_errors))
-			}
+        impl std::fmt::Display for CommandEnum {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(CommandEnum::$v(v) => write!(f,"{v}"),)*
+                }
+            }
+        }
+
+        impl CommandEnum {
+            /// Parses the statement as the command that owns `keyword`, if any does.
+            pub fn parse_keyword(keyword: &str, stmt: &ast::Stmt) -> Option<Result<CommandEnum>> {
+                $(
+                    if <$v as Parse>::KEYWORDS.contains(&keyword) {
+                        return Some(<$v as Parse>::parse(stmt).map(CommandEnum::$v));
+                    }
+                )*
+                None
+            }
+
+            pub fn has_keyword(keyword: &str) -> bool {
+                false $(|| <$v as Parse>::KEYWORDS.contains(&keyword))*
+            }
         }
 
-        pub const COMM_NAMES_DESCRIPTIONS: [(Option<&'static str>,&'static str,&'static str);${count(v)}] = [$(
+        pub const COMM_NAMES_DESCRIPTIONS: [(Option<&'static str>,&'static str,&'static str);${count($v)}] = [$(
             (<$v as CommandDesc>::SHORT_NAME,<$v as CommandDesc>::NAME,<$v as CommandDesc>::DESCRIPTION),
         )*];
     }
@@ -66,11 +110,12 @@ macro_rules! command_enum {
 
 command_enum!{
     pub enum CommandEnum {
-        Drop, Dup, Swap, Reverse, Repeat, Chain, Conditional, Break, Input, Display, Print
+        Drop, Dup, Swap, Reverse, Pack, Repeat, Chain, Conditional, Break, Input, Display, Print, Def, Call, Register, Quote, Combinator, Include
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Break;
 
 impl CommandDesc for Break {
@@ -79,24 +124,30 @@ impl CommandDesc for Break {
     const DESCRIPTION: &'static str = "Unconditionally produces an error. Useful for exiting infinite loops.";
 }
 
-impl FromStr for Break {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.trim().to_uppercase().as_str() == "BREAK" {
-            Ok(Self)
-        } else {
-            Err(Error::ParseToken(Self::NAME))
+impl Parse for Break {
+    const KEYWORDS: &'static [&'static str] = &["BREAK"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [] => Ok(Break),
+            _ => Err(Error::BadArgs(Self::NAME))
         }
     }
 }
 
+impl std::fmt::Display for Break {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"break")
+    }
+}
+
 impl Command for Break {
-    fn comm(self, _: &mut Vec<f64>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, _: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
         Err(Error::Break)
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum Drop {
     Some(usize),
     All
@@ -108,38 +159,30 @@ impl CommandDesc for Drop {
     const DESCRIPTION: &'static str = "Takes a number of items to drop from the bottom of the stack. If the argument is \"all\", all values will be dropped. Otherwise drops one.";
 }
 
-impl FromStr for Drop {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.trim().split_once(' ') {
-            Some((drop_key,drop_arg)) => {
-                let drop_key_trim_up = drop_key.trim().to_uppercase();
-                let drop_arg_trim_up = drop_arg.trim().to_uppercase();
-                if drop_key_trim_up == "DROP" || drop_key_trim_up == "D" {
-                    if drop_arg_trim_up == "ALL" {
-                        Ok(Drop::All)
-                    } else {
-                        let amount = drop_arg_trim_up.parse::<usize>()?;
-                        Ok(Drop::Some(amount))
-                    }
-                } else {
-                    Err(Error::ParseToken(Self::NAME))
-                }
-            },
-            None => {
-                let s_trim_up = s.trim().to_uppercase();
-                if s_trim_up == "DROP" || s_trim_up == "D" {
-                    Ok(Drop::Some(1))
-                } else {
-                    Err(Error::ParseToken(Self::NAME))
-                }
-            }
+impl Parse for Drop {
+    const KEYWORDS: &'static [&'static str] = &["DROP","D"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [] => Ok(Drop::Some(1)),
+            [arg] if arg.is_word("ALL") => Ok(Drop::All),
+            [arg] => arg.as_usize().map(Drop::Some).ok_or(Error::BadArgs(Self::NAME)),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Drop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Drop::Some(1) => write!(f,"drop"),
+            Drop::Some(amount) => write!(f,"drop {amount}"),
+            Drop::All => write!(f,"drop all")
         }
     }
 }
 
 impl Command for Drop {
-    fn comm(self, stack: &mut Vec<f64>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
         match self {
             Drop::Some(amount) => {
                 let stack_len = stack.len();
@@ -162,6 +205,7 @@ impl Command for Drop {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Dup(pub usize);
 
 impl CommandDesc for Dup {
@@ -170,38 +214,32 @@ impl CommandDesc for Dup {
     const DESCRIPTION: &'static str = "Duplicates the last element in the stack a specified amount of times, or by default one.";
 }
 
-impl FromStr for Dup {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self> {
-        match s.trim().split_once(' ') {
-            Some((dup_key,dup_arg)) => {
-                let dup_key_trim_up = dup_key.trim().to_uppercase();
-                let dup_arg_trim = dup_arg.trim();
-                if dup_key_trim_up == "DUP" || dup_key_trim_up == "DUPLICATE" {
-                    let amount = dup_arg_trim.parse::<usize>()?;
-                    Ok(Dup(amount))
-                } else {
-                    Err(Error::ParseToken(Self::NAME))
-                }
-            },
-            None => {
-                let s_trim_up = s.trim().to_uppercase();
-                if s_trim_up == "DUP" || s_trim_up == "DUPLICATE" {
-                    Ok(Dup(1))
-                } else {
-                    Err(Error::ParseToken(Self::NAME))
-                }
-            }
+impl Parse for Dup {
+    const KEYWORDS: &'static [&'static str] = &["DUP","DUPLICATE"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [] => Ok(Dup(1)),
+            [arg] => arg.as_usize().map(Dup).ok_or(Error::BadArgs(Self::NAME)),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Dup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            1 => write!(f,"dup"),
+            amount => write!(f,"dup {amount}")
         }
     }
 }
 
 impl Command for Dup {
-    fn comm(self, stack: &mut Vec<f64>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
         let Dup(amount) = self;
-        if let Some(&last_elm) = stack.last() {
+        if let Some(last_elm) = stack.last().cloned() {
             for _ in 0..amount {
-                stack.push(last_elm);
+                stack.push(last_elm.clone());
             }
             Ok(None)
         } else {
@@ -211,6 +249,54 @@ impl Command for Dup {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Pack(pub array::Shape);
+
+impl CommandDesc for Pack {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "Vec <int> | Mat <int> <int>";
+    const DESCRIPTION: &'static str = "Takes the specified amount of numbers from the bottom of the stack into a vector, or into a matrix with the specified rows and columns, filled row by row.";
+}
+
+impl Parse for Pack {
+    const KEYWORDS: &'static [&'static str] = &["VEC","MAT"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match (stmt.keyword().as_deref(),stmt.args()) {
+            (Some("VEC"),[len]) => len.as_usize().map(|len| Pack(array::Shape::Vector(len))).ok_or(Error::BadArgs(Self::NAME)),
+            (Some("MAT"),[rows,cols]) => match (rows.as_usize(),cols.as_usize()) {
+                (Some(rows),Some(cols)) => Ok(Pack(array::Shape::Matrix(rows,cols))),
+                _ => Err(Error::BadArgs(Self::NAME))
+            },
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Pack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            array::Shape::Vector(len) => write!(f,"vec {len}"),
+            array::Shape::Matrix(rows,cols) => write!(f,"mat {rows} {cols}")
+        }
+    }
+}
+
+impl Command for Pack {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        let Pack(shape) = self;
+        let stack_len = stack.len();
+        if stack_len < shape.len() {
+            return Err(Error::StackEmpty(stack_len,shape.len()));
+        }
+        let data = stack[stack_len - shape.len()..].iter().map(Value::as_num).collect::<Result<Vec<_>>>()?;
+        stack.truncate(stack_len - shape.len());
+        stack.push(Value::Array(array::Array { shape, data }));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum Swap {
     Specified(usize,usize),
     LastTwo
@@ -222,37 +308,31 @@ impl CommandDesc for Swap {
     const DESCRIPTION: &'static str = "Swaps the position of any two values in the stack. If argument is not provided, it swaps the last element with the previous one";
 }
 
-impl FromStr for Swap {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self> {
-        let trim_up_s = s.trim().to_uppercase();
-        match trim_up_s.split_once(' ').map(|(key,rest)| {
-            let (from,to) = rest.trim().split_once(' ')?;
-            Some((key.trim(),from.trim(),to.trim()))
-        }) {
-            Some(Some((key,from,to))) => {
-                let key_upper = key.to_uppercase();
-                if key_upper == "SWAP" || key_upper == "S" {
-                    let from_int = from.parse::<usize>()?;
-                    let to_int = to.parse::<usize>()?;
-                    Ok(Swap::Specified(from_int,to_int))
-                } else {
-                    Err(Error::ParseToken(Self::NAME))
-                }
+impl Parse for Swap {
+    const KEYWORDS: &'static [&'static str] = &["SWAP","S"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [] => Ok(Swap::LastTwo),
+            [from,to] => match (from.as_usize(),to.as_usize()) {
+                (Some(from),Some(to)) => Ok(Swap::Specified(from,to)),
+                _ => Err(Error::BadArgs(Self::NAME))
             },
-            Some(None) | None => {
-                if trim_up_s == "SWAP" || trim_up_s == "S" {
-                    Ok(Swap::LastTwo)
-                } else {
-                    Err(Error::ParseToken(Self::NAME))
-                }
-            }
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Swap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Swap::Specified(from,to) => write!(f,"swap {from} {to}"),
+            Swap::LastTwo => write!(f,"swap")
         }
     }
 }
 
 impl Command for Swap {
-    fn comm(self, stack: &mut Vec<f64>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
         match self {
             Swap::Specified(from, to) => {
                 let stack_len = stack.len();
@@ -281,6 +361,7 @@ impl Command for Swap {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Reverse;
 
 impl CommandDesc for Reverse {
@@ -289,19 +370,24 @@ impl CommandDesc for Reverse {
     const DESCRIPTION: &'static str = "Reverses the order of the stack";
 }
 
-impl FromStr for Reverse {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s_trim_up = s.trim().to_uppercase();
-        match s_trim_up.as_str() {
-            "REV" | "REVERSE" => Ok(Reverse),
-            _ => Err(Error::ParseToken(Self::NAME))
+impl Parse for Reverse {
+    const KEYWORDS: &'static [&'static str] = &["REV","REVERSE"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [] => Ok(Reverse),
+            _ => Err(Error::BadArgs(Self::NAME))
         }
     }
 }
 
+impl std::fmt::Display for Reverse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"rev")
+    }
+}
+
 impl Command for Reverse {
-    fn comm(self, stack: &mut Vec<f64>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
         stack.reverse();
         Ok(None)
     }
@@ -310,135 +396,217 @@ impl Command for Reverse {
 /* Disabled until a rustc bug is fixed: */
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum CommandOrOp {
-    Command(CommandEnum),
-    Op(ops::OpEnum)
+    /// The command, where it was parsed from, and the comments written in its statement
+    Command(CommandEnum,Span,Vec<ast::Comment>),
+    Op(ops::OpEnum,Span,Vec<ast::Comment>)
+}
+
+impl CommandOrOp {
+    /// Where in the source this was parsed from.
+    pub fn span(&self) -> Span {
+        match self {
+            CommandOrOp::Command(_,span,_) | CommandOrOp::Op(_,span,_) => *span
+        }
+    }
+
+    pub fn comments(&self) -> &[ast::Comment] {
+        match self {
+            CommandOrOp::Command(_,_,comments) | CommandOrOp::Op(_,_,comments) => comments
+        }
+    }
+}
+
+impl Parse for CommandOrOp {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        let span = stmt.1;
+        let comments = || stmt.2.clone();
+        (|| {
+            match stmt.0.first() {
+                None => Ok(CommandOrOp::Op(ops::OpEnum::NOP(ops::NOP::parse(stmt)?),span,comments())),
+                Some(ast::Atom::Num(..)) if stmt.0.len() > 1 => Ok(CommandOrOp::Op(ops::OpEnum::InsQuantity(ops::InsQuantity::parse(stmt)?),span,comments())),
+                Some(ast::Atom::Num(..)) => Ok(CommandOrOp::Op(ops::OpEnum::InsNum(ops::InsNum::parse(stmt)?),span,comments())),
+                Some(ast::Atom::Frac(_,_)) => Ok(CommandOrOp::Op(ops::OpEnum::InsFrac(ops::InsFrac::parse(stmt)?),span,comments())),
+                Some(ast::Atom::Complex(_,_)) => Ok(CommandOrOp::Op(ops::OpEnum::InsComplex(ops::InsComplex::parse(stmt)?),span,comments())),
+                Some(ast::Atom::Time(_)) => Ok(CommandOrOp::Op(ops::OpEnum::InsTime(ops::InsTime::parse(stmt)?),span,comments())),
+                Some(ast::Atom::PlusMinus(_,_)) => Ok(CommandOrOp::Op(ops::OpEnum::InsPlusMinus(ops::InsPlusMinus::parse(stmt)?),span,comments())),
+                Some(ast::Atom::Group(group)) if matches!(group.as_slice(), [ast::Atom::Num(..),ast::Atom::Num(..)]) => Ok(CommandOrOp::Op(ops::OpEnum::InsComplex(ops::InsComplex::parse(stmt)?),span,comments())),
+                Some(ast::Atom::Block(_)) => match ops::InsArray::literal(stmt) {
+                    Some(array) => Ok(CommandOrOp::Op(ops::OpEnum::InsArray(array?),span,comments())),
+                    None => Ok(CommandOrOp::Command(CommandEnum::Quote(Quote::parse(stmt)?),span,comments()))
+                },
+                Some(ast::Atom::Word(w)) => {
+                    let keyword = w.to_uppercase();
+                    CommandEnum::parse_keyword(&keyword,stmt).map(|c| c.map(|c| CommandOrOp::Command(c,span,comments())))
+                        .or_else(|| ops::OpEnum::parse_keyword(&keyword,stmt).map(|o| o.map(|o| CommandOrOp::Op(o,span,comments()))))
+                        .unwrap_or_else(|| Call::parse(stmt).map(|c| CommandOrOp::Command(CommandEnum::Call(c),span,comments())))
+                },
+                Some(ast::Atom::Str(_)) => Err(Error::Expected("a command or operation","a string".to_string())),
+                Some(ast::Atom::Group(_)) => Err(Error::Expected("a command or operation","parenthesized arguments".to_string()))
+            }
+        })().map_err(|e: Error| e.at(span))
+    }
+}
+
+impl std::fmt::Display for CommandOrOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_source(f,"")
+    }
+}
+
+impl CommandOrOp {
+    /// Comments written after the statement, on the line it ends.
+    fn trailing_comments(&self) -> &[ast::Comment] {
+        let span = self.span();
+        let comments = self.comments();
+        let leading = comments.iter().take_while(|c| span.start == span.end || c.span().start < span.end).count();
+        &comments[leading..]
+    }
+
+    /// Writes the source of the statement followed by `terminator`, with its comments on the
+    /// lines before it, or after it on the same line for those that trail it.
+    fn write_source(&self, f: &mut std::fmt::Formatter<'_>, terminator: &str) -> std::fmt::Result {
+        let trailing = self.trailing_comments();
+        for comment in &self.comments()[..self.comments().len() - trailing.len()] {
+            writeln!(f,"{comment}")?;
+        }
+        match self {
+            CommandOrOp::Command(c,_,_) => write!(f,"{c}{terminator}")?,
+            CommandOrOp::Op(o,_,_) => write!(f,"{o}{terminator}")?
+        }
+        for comment in trailing {
+            write!(f," {comment}")?;
+        }
+        Ok(())
+    }
 }
 
 impl FromStr for CommandOrOp {
     type Err = Error;
+    /// Parses a line of input. Several statements separated by semicolons become a chain.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut errors = vec![];
-        match s.parse::<CommandEnum>() {
-            Ok(v) => return Ok(CommandOrOp::Command(v)),
-            Err(e) => {
-                errors.push(e);
-                match s.parse::<ops::OpEnum>() {
-                    Ok(v) => return Ok(CommandOrOp::Op(v)),
-                    Err(e) => errors.push(e)
-                }
-            }
+        let ast::Program(mut stmts) = parser::parse(s)?;
+        if stmts.len() == 1 {
+            CommandOrOp::parse(&stmts.remove(0))
+        } else {
+            let span = stmts[0].1.to(stmts[stmts.len() - 1].1);
+            Ok(CommandOrOp::Command(CommandEnum::Chain(Chain::from_stmts(&stmts)?),span,vec![]))
         }
-        Err(Error::ParseEnum(errors))
     }
 }
 
 impl Command for CommandOrOp {
-    fn comm(self, stack: &mut Vec<f64>, stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>> {
         match self {
-            CommandOrOp::Command(c) => c.comm(stack,stdin,stdout),
-            CommandOrOp::Op(o) => o.comm(stack,stdin,stdout)
+            CommandOrOp::Command(c,span,_) => c.comm(stack,ctx,stdin,stdout).map_err(|e| e.at(span)),
+            CommandOrOp::Op(o,span,_) => o.comm(stack,ctx,stdin,stdout).map_err(|e| e.at(span))
         }
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum Repeat {
     Bounded(usize,Box<CommandOrOp>),
-    Unbounded(Box<CommandOrOp>)
+    Unbounded(Box<CommandOrOp>),
+    While([Chain;2]),
+    /// Start, inclusive end and step of the index, the register it is stored in if not pushed,
+    /// and the body
+    For([f64;3],Option<String>,Chain)
 }
 
 impl CommandDesc for Repeat {
-    const SHORT_NAME: Option<&'static str> = Some("R(int) | R");
-    const NAME: &'static str = "Repeat(int) | Repeat";
-    const DESCRIPTION: &'static str = "Repeats a command a specified number of times, or if argument not provided, until an error is yielded or a precompiled limit is reached";
+    const SHORT_NAME: Option<&'static str> = Some("R(int) | R | While [ <Condition> ] [ <Body> ] | For(start, end, step) [Name] [ <Body> ]");
+    const NAME: &'static str = "Repeat(int) | Repeat | While [ <Condition> ] [ <Body> ] | For(start, end, step) [Name] [ <Body> ]";
+    const DESCRIPTION: &'static str = "Repeats a command a specified number of times, or if argument not provided, until an error is yielded or a precompiled limit is reached. While runs the condition, pops its result, and runs the body as long as that result is nonzero, up to the same limit. For runs the body once per index from start to end inclusive, pushing the index first, or storing it in the named register if one is given. Step defaults to 1.";
 }
 
-impl FromStr for Repeat {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        #[derive(Clone)]
-        enum RepeatParseState {
-            Start,
-            Key(bool),
-            KeyParenOpen(String),
-            ParenStop(usize,String),
-            NoParenStop(String),
-            KeyError
-        }
-        use RepeatParseState::*;
-        let mut parsing_state = Start;
-        let s_trim_up = s.trim().to_uppercase();
-        let mut s_chars = s_trim_up.chars();
-        while let Some(c) = s_chars.next() {
-            parsing_state = match parsing_state.clone() {
-                Start => if c.to_string().to_uppercase() == "R" {
-                    Key(false)
-                } else {
-                    KeyError
-                },
-                Key(is_known_long) => match c.to_string().to_uppercase().as_str() {
-                    "E" => if is_known_long {
-                            KeyError
-                    } else {
-                        let mut successful = true;
-                        for expected_c in "PEAT".chars() {
-                            match s_chars.next() {
-                                Some(actual_c) => if expected_c != actual_c {
-                                    successful = false;
-                                    break;
-                                },
-                                None => { successful = false; break; }
-                            }
-                        }
-                        if successful {
-                            Key(true)
-                        } else {
-                            KeyError
-                        }
-                    },
-                    "(" => KeyParenOpen(String::new()),
-                    " " => {
-                        let mut out = String::new();
-                        while let Some(c) = s_chars.next() {
-                            out.push(c);
-                        }
-                        NoParenStop(out)
-                    },
-                    _ => KeyError
-                },
-                KeyParenOpen(mut partial_str) => if c.is_alphanumeric() {
-                    partial_str.push(c);
-                    KeyParenOpen(partial_str)
-                } else if c == ')' {
-                    let amount = partial_str.parse::<usize>()?;
-                    if !(matches!(s_chars.next(),Some(' '))) {
-                        KeyError
-                    } else {
-                        let mut rest_str = String::new();
-                        while let Some(inner_c) = s_chars.next() {
-                            rest_str.push(inner_c);
-                        }
-                        ParenStop(amount,rest_str)
-                    }
-                } else {
-                    KeyError
+impl Parse for Repeat {
+    const KEYWORDS: &'static [&'static str] = &["REPEAT","R","WHILE","FOR"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        if stmt.keyword().as_deref() == Some("WHILE") {
+            return match stmt.args() {
+                [ast::Atom::Block(ast::Block(cond)),ast::Atom::Block(ast::Block(body))] =>
+                    Ok(Repeat::While([Chain::from_stmts(cond)?,Chain::from_stmts(body)?])),
+                _ => Err(Error::BadArgs(Self::NAME))
+            };
+        }
+        if stmt.keyword().as_deref() == Some("FOR") {
+            let (bounds,register,body) = match stmt.args() {
+                [ast::Atom::Group(bounds),ast::Atom::Block(ast::Block(body))] => (bounds,None,body),
+                [ast::Atom::Group(bounds),ast::Atom::Word(name),ast::Atom::Block(ast::Block(body))] => (bounds,Some(name.to_uppercase()),body),
+                _ => return Err(Error::BadArgs(Self::NAME))
+            };
+            let bounds = match bounds.as_slice() {
+                [ast::Atom::Num(start,_),ast::Atom::Num(end,_)] => [*start,*end,1.0],
+                [ast::Atom::Num(start,_),ast::Atom::Num(end,_),ast::Atom::Num(step,_)] if *step != 0.0 => [*start,*end,*step],
+                _ => return Err(Error::BadArgs(Self::NAME))
+            };
+            return Ok(Repeat::For(bounds,register,Chain::from_stmts(body)?));
+        }
+        match stmt.args() {
+            [ast::Atom::Group(count), body @ ..] => match count.as_slice() {
+                [count] => {
+                    let amount = count.as_usize().ok_or(Error::BadArgs(Self::NAME))?;
+                    Ok(Repeat::Bounded(amount,Box::new(Self::body(stmt,body)?)))
                 },
-                ParenStop(_,_) | NoParenStop(_) => panic!("State machine did not consume iterator before stopping"),
-                KeyError  =>  break
+                _ => Err(Error::BadArgs(Self::NAME))
+            },
+            body => Ok(Repeat::Unbounded(Box::new(Self::body(stmt,body)?)))
+        }
+    }
+}
+
+impl std::fmt::Display for Repeat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Repeat::Bounded(amount,body) => write!(f,"repeat({amount}){}",Self::body_source(body)),
+            Repeat::Unbounded(body) => write!(f,"repeat{}",Self::body_source(body)),
+            Repeat::While([cond,body]) => write!(f,"while {} {}",cond.block(),body.block()),
+            Repeat::For([start,end,step],register,body) => {
+                write!(f,"for({}, {}",lexer::num_source(*start),lexer::num_source(*end))?;
+                if *step != 1.0 {
+                    write!(f,", {}",lexer::num_source(*step))?;
+                }
+                write!(f,")")?;
+                if let Some(name) = register {
+                    write!(f," {}",name.to_lowercase())?;
+                }
+                write!(f," {}",body.block())
             }
         }
-        match parsing_state {
-            KeyError | Start => Err(Error::ParseToken(Self::NAME)),
-            ParenStop(amount,rest) => Ok(Repeat::Bounded(amount,Box::new(rest.parse()?))),
-            NoParenStop(rest) => Ok(Repeat::Unbounded(Box::new(rest.parse()?))),
-            _ => unreachable!()
+    }
+}
+
+impl Repeat {
+    /// Parses what follows `R` or `R(int)`, where a lone block is run rather than quoted.
+    fn body(stmt: &ast::Stmt, body: &[ast::Atom]) -> Result<CommandOrOp> {
+        match body {
+            [ast::Atom::Block(ast::Block(stmts))] => Ok(CommandOrOp::Command(CommandEnum::Chain(Chain::from_stmts(stmts)?),stmt.1,vec![])),
+            body => CommandOrOp::parse(&ast::Stmt(body.to_vec(),stmt.1,vec![]))
+        }
+    }
+
+    /// Index of a `for` loop at an iteration counting from 0, computed by the backend so that an
+    /// exact one steps without rounding.
+    fn index<N: Number>(start: f64, step: f64, iteration: usize) -> N {
+        N::from_literal(start) + N::from_f64(iteration as f64) * N::from_literal(step)
+    }
+
+    /// Source of the body as written after `repeat`, with its leading space.
+    fn body_source(body: &CommandOrOp) -> String {
+        match body {
+            CommandOrOp::Command(CommandEnum::Chain(chain),_,_) => format!(" {}",chain.block()),
+            CommandOrOp::Op(ops::OpEnum::NOP(_),_,_) => String::new(),
+            body => format!(" {body}")
         }
     }
 }
 
 impl Command for Repeat {
-    fn comm(self, stack: &mut Vec<f64>, mut stdin: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, mut stdin: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
         match self {
             Repeat::Unbounded(c) => {
                 let mut rep_count = 0;
@@ -446,7 +614,7 @@ impl Command for Repeat {
                     if rep_count >= MAX_REPETITIONS {
                         break Err(Error::InfLoop)
                     }
-                    match c.clone().comm(stack,Box::new(&mut stdin) as Box<dyn std::io::Read>, Box::new(&mut stdout) as Box<dyn std::io::Write>) {
+                    match c.clone().comm(stack,ctx,Box::new(&mut stdin) as Box<dyn std::io::Read>, Box::new(&mut stdout) as Box<dyn std::io::Write>) {
                         Ok(_) => (),
                         Err(e) => break Ok(Some(format!("Ended Repetitions with the following error: \n{e}")))
                     }
@@ -456,81 +624,121 @@ impl Command for Repeat {
             Repeat::Bounded(reps, c) => {
                 let mut output = Ok(None);
                 for _ in 0..reps {
-                    output = c.clone().comm(stack, Box::new(&mut stdin) as Box<dyn std::io::Read>, Box::new(&mut stdout) as Box<dyn std::io::Write>);
+                    output = c.clone().comm(stack, ctx, Box::new(&mut stdin) as Box<dyn std::io::Read>, Box::new(&mut stdout) as Box<dyn std::io::Write>);
                     if output.is_err() { break; }
                 }
                 output
+            },
+            Repeat::While([cond,body]) => {
+                let mut output = None;
+                for _ in 0..MAX_REPETITIONS {
+                    cond.clone().comm(stack, ctx, Box::new(&mut stdin) as Box<dyn std::io::Read>, Box::new(&mut stdout) as Box<dyn std::io::Write>)?;
+                    if pop_num(stack)?.is_zero() {
+                        return Ok(output);
+                    }
+                    output = body.clone().comm(stack, ctx, Box::new(&mut stdin) as Box<dyn std::io::Read>, Box::new(&mut stdout) as Box<dyn std::io::Write>)?;
+                }
+                Err(Error::InfLoop)
+            },
+            Repeat::For([start,end,step],register,body) => {
+                let reps = ((end - start) / step).floor() + 1.0;
+                if reps.is_nan() || reps >= MAX_REPETITIONS as f64 {
+                    return Err(Error::InfLoop);
+                }
+                let mut output = None;
+                for iteration in 0..(reps.max(0.0) as usize) {
+                    let index = start + iteration as f64 * step;
+                    match &register {
+                        Some(name) => { ctx.registers.insert(name.clone(),Value::Num(Repeat::index(start,step,iteration))); },
+                        None => stack.push(Value::Num(Repeat::index(start,step,iteration)))
+                    }
+                    output = body.clone().comm(stack, ctx, Box::new(&mut stdin) as Box<dyn std::io::Read>, Box::new(&mut stdout) as Box<dyn std::io::Write>)
+                        .map_err(|e| e.in_iteration(iteration + 1,index))?;
+                }
+                Ok(output)
             }
         }
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Chain(pub Vec<CommandOrOp>);
 
 impl Chain {
+    /// Parses a whole script, which is a chain without the enclosing brackets.
     pub fn from_bare(input: &str) -> Result<Self> {
-        let mut inner_pieces = vec![];
-        let mut brackets: usize = 0;
-        let mut curr_piece = String::new();
-        macro_rules! bail { () => {{ return Err(Error::UnbBraces) }} }
-        for c in input.trim().chars() {
-            match c {
-                '[' => { brackets += 1; curr_piece.push('['); },
-                ']' => if brackets == 0 {
-                    bail!()
-                } else {
-                    brackets -= 1;
-                    curr_piece.push(']')
-                },
-                ';' if brackets == 0 => inner_pieces.push(std::mem::take(&mut curr_piece)),
-                c => curr_piece.push(c)
-            }
-        }
-        inner_pieces.push(curr_piece);
-        if brackets != 0 {
-            bail!()
+        let ast::Program(stmts) = parser::parse(input)?;
+        Self::from_stmts(&stmts)
+    }
+
+    pub fn from_stmts(stmts: &[ast::Stmt]) -> Result<Self> {
+        Ok(Chain(stmts.iter().map(CommandOrOp::parse).collect::<Result<Vec<_>>>()?))
+    }
+
+    /// Source of the chain between brackets. Short chains that fit on one line are kept on it,
+    /// others get a line per statement, indented one level.
+    pub fn block(&self) -> String {
+        let inline = self.0.iter().map(|c| c.to_string()).collect::<Vec<_>>().join("; ");
+        if inline.trim_end().is_empty() {
+            "[]".to_string()
+        } else if !inline.contains('\n') && inline.len() <= MAX_INLINE_BLOCK && self.0.iter().all(|c| c.trailing_comments().is_empty()) {
+            format!("[ {} ]",inline.trim_end())
+        } else {
+            format!("[\n{}\n]",indent(&self.to_string()).trim_end_matches('\n'))
         }
-        Ok(Chain(inner_pieces.into_iter().map(|inner_piece| inner_piece.parse::<CommandOrOp>()).collect::<Result<Vec<_>,_>>()?))
     }
 }
 
 impl CommandDesc for Chain {
     const SHORT_NAME: Option<&'static str> = None;
-    const NAME: &'static str = "[ <Command Or Op>; ... ]";
-    const DESCRIPTION: &'static str = "Allows you to chain commands or operations together";
+    const NAME: &'static str = "<Command Or Op>; <Command Or Op>; ...";
+    const DESCRIPTION: &'static str = "Allows you to chain commands or operations together. Blocks given to commands like Repeat or If are chains too";
 }
 
-impl FromStr for Chain {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s_trim = s.trim();
-        if s.starts_with('[') && s.ends_with(']') {
-            let inner_text = &s_trim[1..(s.len() - 1)];
-            Self::from_bare(inner_text)
-        } else {
-            Err(Error::ParseToken(Self::NAME))
+impl Parse for Chain {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::Block(ast::Block(stmts))] => Self::from_stmts(stmts),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i,c) in self.0.iter().enumerate() {
+            if i + 1 < self.0.len() {
+                c.write_source(f,";")?;
+                writeln!(f)?;
+            } else {
+                c.write_source(f,"")?;
+            }
         }
+        Ok(())
     }
 }
 
 impl Command for Chain {
-    fn comm(self, stack: &mut Vec<f64>, mut stdin: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, mut stdin: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
         let mut out = None;
         for c in self.0 {
-            out = c.comm(stack,Box::new(&mut stdin) as Box<dyn std::io::Read>,Box::new(&mut stdout) as Box<dyn std::io::Write>)?;
+            out = c.comm(stack,ctx,Box::new(&mut stdin) as Box<dyn std::io::Read>,Box::new(&mut stdout) as Box<dyn std::io::Write>)?;
         }
         Ok(out)
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum ConditionalKind {
     If,
     Try
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Conditional(pub ConditionalKind,pub [Chain;2]);
 
 impl CommandDesc for Conditional {
@@ -539,106 +747,41 @@ impl CommandDesc for Conditional {
     const DESCRIPTION: &'static str = "If: Pops the value at the top of the stack, executes the first command if nonzero, otherwise the other. Try: Runs the first command. If an error occurs during execution, the command is interrupted and the second command is run.";
 }
 
-impl FromStr for Conditional {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let trim_up_s = s.trim().to_uppercase();
-        let mut trim_up_s_chars = trim_up_s.chars();
-        #[derive(Clone)]
-        enum ParseState {
-            Start,
-            First(ConditionalKind,String),
-            Second(ConditionalKind,[String;2]),
-            End(ConditionalKind,[String;2])
-        }
-        use ParseState::*;
-        let mut brackets: usize = 1;
-        let mut state = Start;
-        macro_rules! bail { () => {{ return Err(Error::ParseToken(Self::NAME)) }} }
-        while let Some(c)  = trim_up_s_chars.next() {
-            state = match state.clone() {
-                Start => {
-                    if let Some(second_char) = trim_up_s_chars.next() {
-                        let cond_kind = if c == 'I' && second_char == 'F' {
-                            ConditionalKind::If
-                        } else if c == 'T' && second_char == 'R' && Some('Y') == trim_up_s_chars.next() {
-                            ConditionalKind::Try
-                        } else { bail!() };
-                        loop {
-                            if let Some(poss_space_c) = trim_up_s_chars.next() {
-                                if !poss_space_c.is_ascii_whitespace() {
-                                    if poss_space_c == '[' {
-                                        break First(cond_kind,poss_space_c.to_string());
-                                    } else { bail!() }
-                                }
-                            } else { bail!() }
-                        }
-                    } else { bail!() }
-                },
-                First(cond_kind,mut s) => {
-                    let mut inner_c = c;
-                    loop {
-                        match inner_c {
-                            '[' => { brackets += 1; s.push('[') },
-                            ']' => if brackets == 1 {
-                                s.push(']');
-                                break loop {
-                                    if let Some(in_bet_whitespace) = trim_up_s_chars.next() {
-                                        if in_bet_whitespace == '[' {
-                                            break Second(cond_kind,[s,'['.to_string()]);
-                                        } else if !in_bet_whitespace.is_ascii_whitespace() { bail!() }
-                                    } else { bail!() }
-                                };
-                            } else { brackets -= 1; s.push(']'); },
-                            c => s.push(c)
-                        };
-                        if let Some(new_inner_c) = trim_up_s_chars.next() {
-                            inner_c = new_inner_c;
-                        } else {
-                            bail!()
-                        }
-                    }
-                },
-                Second(cond_kind,[s_first,mut s]) => {
-                    let mut inner_c = c;
-                    loop {
-                        match inner_c {
-                            '[' => { brackets += 1; s.push('[') },
-                            ']' => if brackets == 1 {
-                                s.push(']');
-                                break End(cond_kind,[s_first,s]);
-                            } else { brackets -= 1; s.push(']') },
-                            c => s.push(c)
-                        }
-                        if let Some(new_inner_c) = trim_up_s_chars.next() {
-                            inner_c = new_inner_c;
-                        } else {
-                            bail!()
-                        }
-                    }
-                },
-                End(_,_) => bail!()
-            }
-        }
-        match state {
-            Start => bail!(),
-            End(cond_kind,[first,second]) => Ok(Conditional(cond_kind,[first.parse::<Chain>()?,second.parse::<Chain>()?])),
-            _ => unreachable!()
+impl Parse for Conditional {
+    const KEYWORDS: &'static [&'static str] = &["IF","TRY"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        let cond_kind = match stmt.keyword().as_deref() {
+            Some("IF") => ConditionalKind::If,
+            _ => ConditionalKind::Try
+        };
+        match stmt.args() {
+            [ast::Atom::Block(ast::Block(first)),ast::Atom::Block(ast::Block(second))] =>
+                Ok(Conditional(cond_kind,[Chain::from_stmts(first)?,Chain::from_stmts(second)?])),
+            _ => Err(Error::BadArgs(Self::NAME))
         }
     }
 }
 
+impl std::fmt::Display for Conditional {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keyword = match self.0 {
+            ConditionalKind::If => "if",
+            ConditionalKind::Try => "try"
+        };
+        write!(f,"{keyword} {} {}",self.1[0].block(),self.1[1].block())
+    }
+}
+
 impl Command for Conditional {
-    fn comm(self, stack: &mut Vec<f64>, mut stdin: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, mut stdin: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
         let [first_chain,second_chain] = self.1;
         match self.0 {
-            ConditionalKind::If => if let Some(v) = stack.pop() {
-                (if v == 0.0 { first_chain } else { second_chain }).comm(stack,stdin,stdout)
-            } else {
-                Err(Error::StackEmpty(stack.len(), 1))
+            ConditionalKind::If => {
+                let v = pop_num(stack)?;
+                (if v.is_zero() { first_chain } else { second_chain }).comm(stack,ctx,stdin,stdout)
             },
-            ConditionalKind::Try => match first_chain.comm(stack,Box::new(&mut stdin) as Box<dyn std::io::Read>,Box::new(&mut stdout) as Box<dyn std::io::Write>) {
-                Err(_) => second_chain.comm(stack,stdin,stdout),
+            ConditionalKind::Try => match first_chain.comm(stack,ctx,Box::new(&mut stdin) as Box<dyn std::io::Read>,Box::new(&mut stdout) as Box<dyn std::io::Write>) {
+                Err(_) => second_chain.comm(stack,ctx,stdin,stdout),
                 Ok(v) => Ok(v)
             }
         }
@@ -647,51 +790,106 @@ impl Command for Conditional {
 }
 
 #[derive(Clone)]
-pub struct Display(pub String);
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Display(pub Vec<Segment>);
+
+/// Piece of the string printed by [`Display`].
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum Segment {
+    Text(String),
+    /// `{0}`, the value at that position counting down from the top of the stack
+    Stack(usize),
+    /// `{x}`, the value held in that register
+    Register(String)
+}
 
 impl CommandDesc for Display {
     const SHORT_NAME: Option<&'static str> = Some("Disp \"<Escaped String>\"");
     const NAME: &'static str = "Display \"<Escaped String>\"";
-    const DESCRIPTION: &'static str = "Prints an escaped string to the command line. Only escaped characters are double quotes and backslashes, both escaped with a preceeding backslash.";
+    const DESCRIPTION: &'static str = "Prints a string to the command line. Escapes are \\\\, \\\", \\n, \\t and \\u{XXXX}. A placeholder such as {0} is replaced by the value at that position from the top of the stack, and {x} by the value of register x, without popping anything. {{ and }} print literal braces.";
 }
 
-impl FromStr for Display {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        macro_rules! bail { () => {{return Err(Error::ParseToken(Self::NAME))}} }
-        let trimmed_s = s.trim();
-        if !s.to_uppercase().starts_with("DISPLAY") { bail!() }
-        let mut new_s = trimmed_s.chars().skip(7).skip_while(|c| c.is_ascii_whitespace()).collect::<String>();
-        if !new_s.ends_with('"') || new_s.ends_with("\\\"") { bail!() }
-        new_s = new_s.chars().rev().skip(1).collect::<Vec<_>>().into_iter().rev().collect::<String>();
-        if !new_s.starts_with('"') { bail!() }
-        new_s = new_s.chars().skip(1).collect::<String>();
-        let mut out = String::with_capacity(new_s.len());
-        let mut new_s_chars = new_s.chars();
-        while let Some(c) = new_s_chars.next() {
+impl Display {
+    fn segments(s: &str) -> Result<Vec<Segment>> {
+        let mut segments = vec![];
+        let mut text = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
             match c {
-                '\\' => if let Some(escaped_c) = new_s_chars.next() {
-                    match escaped_c {
-                        '\\' => out.push('\\'),
-                        '"'  => out.push('"'),
-                        _ => bail!()
+                '{' if chars.as_str().starts_with('{') => { chars.next(); text.push('{') },
+                '}' if chars.as_str().starts_with('}') => { chars.next(); text.push('}') },
+                '{' => {
+                    let (name,rest) = chars.as_str().split_once('}').ok_or_else(|| Error::Placeholder(format!("{{{}",chars.as_str())))?;
+                    let segment = match name.parse::<usize>() {
+                        Ok(index) => Segment::Stack(index),
+                        Err(_) if !name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c == '{') => Segment::Register(name.to_uppercase()),
+                        Err(_) => return Err(Error::Placeholder(format!("{{{name}}}")))
+                    };
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
                     }
-                } else { bail!() },
-                _ => out.push(c)
+                    segments.push(segment);
+                    chars = rest.chars();
+                },
+                '}' => return Err(Error::Placeholder("}".to_string())),
+                c => text.push(c)
             }
         }
-        Ok(Display(out))
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(segments)
+    }
+}
+
+impl Parse for Display {
+    const KEYWORDS: &'static [&'static str] = &["DISPLAY","DISP"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [ast::Atom::Str(s)] => Ok(Display(Self::segments(s)?)),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Display {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(t) => text.push_str(&t.replace('{',"{{").replace('}',"}}")),
+                Segment::Stack(index) => text.push_str(&format!("{{{index}}}")),
+                Segment::Register(name) => text.push_str(&format!("{{{}}}",name.to_lowercase()))
+            }
+        }
+        write!(f,"display {}",lexer::quote_str(&text))
     }
 }
 
 impl Command for Display {
-    fn comm(self, _: &mut Vec<f64>, _: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
-        writeln!(stdout,"{}",self.0)?;
-        Ok(Some(self.0))
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, _: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
+        let mut out = String::new();
+        for segment in self.0 {
+            match segment {
+                Segment::Text(text) => out.push_str(&text),
+                Segment::Stack(index) => {
+                    let v = stack.iter().rev().nth(index).ok_or(Error::OOB(index,stack.len()))?;
+                    out.push_str(v.to_string().trim_start());
+                },
+                Segment::Register(name) => {
+                    let v = ctx.registers.get(&name).ok_or(Error::UnknownRegister(name))?;
+                    out.push_str(v.to_string().trim_start());
+                }
+            }
+        }
+        writeln!(stdout,"{out}")?;
+        Ok(Some(out))
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Input;
 
 impl CommandDesc for Input {
@@ -700,27 +898,34 @@ impl CommandDesc for Input {
     const DESCRIPTION: &'static str = "Allows the user to input a floating point number, and puts it on the stack. Mostly used in scripts.";
 }
 
-impl FromStr for Input {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s_trim_up = s.trim().to_uppercase();
-        match s_trim_up.as_str() {
-            "I" | "INPUT" => Ok(Self),
-            _ => Err(Error::ParseToken(Self::NAME))
+impl Parse for Input {
+    const KEYWORDS: &'static [&'static str] = &["INPUT","I"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [] => Ok(Input),
+            _ => Err(Error::BadArgs(Self::NAME))
         }
     }
 }
 
+impl std::fmt::Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"input")
+    }
+}
+
 impl Command for Input {
-    fn comm(self, stack: &mut Vec<f64>, mut stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, mut stdin: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
         let mut buf = String::new();
         use std::io::BufRead;
         std::io::BufReader::new(&mut stdin).read_line(&mut buf)?;
-        Ok(buf.trim().parse::<ops::InsNum>()?.comm(stack,stdin,stdout)?)
+        stack.push(Value::Num(N::parse(buf.trim())?));
+        Ok(None)
     }
 }
 
 #[derive(Clone,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Print;
 
 impl CommandDesc for Print {
@@ -729,47 +934,369 @@ impl CommandDesc for Print {
     const DESCRIPTION: &'static str = "Prints the top number in the stack to the screen. Mostly used in scripts.";
 }
 
-impl FromStr for Print {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.trim().to_uppercase().as_str() {
-            "P" | "PRINT" => Ok(Print),
-            _ => Err(Error::ParseToken(Self::NAME))
+impl Parse for Print {
+    const KEYWORDS: &'static [&'static str] = &["PRINT","P"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [] => Ok(Print),
+            _ => Err(Error::BadArgs(Self::NAME))
         }
     }
 }
 
+impl std::fmt::Display for Print {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"print")
+    }
+}
+
 impl Command for Print {
-    fn comm(self, stack: &mut Vec<f64>, _: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
-        let f_num = format_num(stack.pop().ok_or(Error::StackEmpty(0, 1))?);
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
+        let f_num = stack.pop().ok_or(Error::StackEmpty(0, 1))?.to_string();
         writeln!(stdout,"{f_num}")?;
         Ok(Some(f_num))
     }
 }
 
-pub fn format_num(input: f64) -> String {
-    let sign = if input.is_sign_positive() { " " } else {"-"};
-    if input.is_finite() {
-        let abs_num = input.abs();
-        let left_digits_len = if abs_num <= 1.0 { 1 } else { abs_num.log10() as usize + 1 };
-        let excess_digits = left_digits_len % 3;
-        let padding_amount = if excess_digits == 0 { 0 } else { 3 - excess_digits };
-        let padding = ' '.to_string().repeat(padding_amount);
-        let num = format!("{padding}{abs_num:.9}");
-        let regrouped_num = num.split('.').map(|side| side.chars()
-            .fold((vec![],vec![]),|(mut tail,mut curr),c| {
-                curr.push(c);
-                if curr.len() == 3 {
-                    tail.push(curr);
-                    (tail,vec![])
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Def(pub String,pub Chain);
+
+impl CommandDesc for Def {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "Def <Name> [ <Command or Op>; ... ]";
+    const DESCRIPTION: &'static str = "Defines a word that runs the given chain whenever its name is entered as a command. A word may call itself, up to a nesting limit.";
+}
+
+impl Parse for Def {
+    const KEYWORDS: &'static [&'static str] = &["DEF"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [ast::Atom::Word(name),ast::Atom::Block(ast::Block(stmts))] => {
+                let keyword = name.to_uppercase();
+                if CommandEnum::has_keyword(&keyword) || ops::OpEnum::has_keyword(&keyword) {
+                    Err(Error::Redefine(name.clone()))
                 } else {
-                    (tail,curr)
+                    Ok(Def(name.clone(),Chain::from_stmts(stmts)?))
                 }
-            }).0.into_iter().map(|three_chunk|
-                three_chunk.into_iter().fold(String::with_capacity(3),|mut acc,c| { acc.push(c); acc })
-            ).intersperse(" ".to_string()).fold(String::new(),|acc,s| acc + &s)
-        ).intersperse(".".to_string()).fold(String::new(),|acc,s| acc + &s);
-        format!("{sign}{regrouped_num}")
+            },
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Def {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"def {} {}",self.0.to_lowercase(),self.1.block())
+    }
+}
+
+impl Command for Def {
+    fn comm<N: Number>(self, _: &mut Vec<Value<N>>, ctx: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        let Def(name,chain) = self;
+        ctx.words.insert(name.to_uppercase(),std::rc::Rc::new(chain));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Call(pub String);
+
+impl CommandDesc for Call {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "<Name>";
+    const DESCRIPTION: &'static str = "Entering the name of a word created with Def runs its chain";
+}
+
+impl Parse for Call {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::Word(name)] => Ok(Call(name.clone())),
+            [ast::Atom::Word(name),..] => Err(Error::UnknownWord(name.clone())),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Call {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}",self.0.to_lowercase())
+    }
+}
+
+impl Command for Call {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>> {
+        let chain = ctx.words.get(&self.0.to_uppercase()).cloned().ok_or(Error::UnknownWord(self.0))?;
+        if ctx.depth >= ctx.max_depth {
+            return Err(Error::RecursionLimit(ctx.max_depth));
+        }
+        ctx.depth += 1;
+        let out = Chain::clone(&chain).comm(stack,ctx,stdin,stdout);
+        ctx.depth -= 1;
+        out
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum Register {
+    Store(String),
+    Recall(String),
+    Purge(String),
+    List
+}
+
+impl CommandDesc for Register {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "Sto <Name> | Rcl <Name> | Purge <Name> | Vars";
+    const DESCRIPTION: &'static str = "Named registers. Sto pops the top of the stack into a register, Rcl pushes a copy of its value, Purge deletes it and Vars prints every register.";
+}
+
+impl Parse for Register {
+    const KEYWORDS: &'static [&'static str] = &["STO","RCL","PURGE","VARS"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match (stmt.keyword().as_deref(),stmt.args()) {
+            (Some("STO"),[ast::Atom::Word(name)]) => Ok(Register::Store(name.to_uppercase())),
+            (Some("RCL"),[ast::Atom::Word(name)]) => Ok(Register::Recall(name.to_uppercase())),
+            (Some("PURGE"),[ast::Atom::Word(name)]) => Ok(Register::Purge(name.to_uppercase())),
+            (Some("VARS"),[]) => Ok(Register::List),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Register::Store(name) => write!(f,"sto {}",name.to_lowercase()),
+            Register::Recall(name) => write!(f,"rcl {}",name.to_lowercase()),
+            Register::Purge(name) => write!(f,"purge {}",name.to_lowercase()),
+            Register::List => write!(f,"vars")
+        }
+    }
+}
+
+impl Command for Register {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, _: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
+        match self {
+            Register::Store(name) => {
+                let v = stack.pop().ok_or(Error::StackEmpty(0, 1))?;
+                ctx.registers.insert(name,v);
+                Ok(None)
+            },
+            Register::Recall(name) => {
+                let v = ctx.registers.get(&name).ok_or(Error::UnknownRegister(name))?;
+                stack.push(v.clone());
+                Ok(None)
+            },
+            Register::Purge(name) => {
+                ctx.registers.remove(&name).ok_or(Error::UnknownRegister(name))?;
+                Ok(None)
+            },
+            Register::List => {
+                let listing = if ctx.registers.is_empty() {
+                    "<No Registers>".to_string()
+                } else {
+                    ctx.registers.iter().map(|(name,v)| format!("{name}: {v}")).intersperse("\n".to_string()).collect::<String>()
+                };
+                writeln!(stdout,"{listing}")?;
+                Ok(Some(listing))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Quote(pub Chain);
+
+impl CommandDesc for Quote {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "[ <Command Or Op>; ... ]";
+    const DESCRIPTION: &'static str = "Pushes the enclosed chain onto the stack as a quotation, without running it";
+}
+
+impl Parse for Quote {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::Block(ast::Block(stmts))] => Ok(Quote(Chain::from_stmts(stmts)?)),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Quote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}",self.0.block())
+    }
+}
+
+impl Command for Quote {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        stack.push(Value::Quote(self.0));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum Combinator {
+    Exec,
+    Dip,
+    Keep,
+    Bi
+}
+
+impl CommandDesc for Combinator {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "Exec | Dip | Keep | Bi";
+    const DESCRIPTION: &'static str = "Run quotations from the stack. Exec pops a quotation and runs it. Dip pops a quotation and the value below, runs the quotation and pushes the value back. Keep is like Dip but also leaves the value for the quotation. Bi pops two quotations and a value, and runs each quotation on its own copy of the value.";
+}
+
+impl Parse for Combinator {
+    const KEYWORDS: &'static [&'static str] = &["EXEC","DIP","KEEP","BI"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match (stmt.keyword().as_deref(),stmt.args()) {
+            (Some("EXEC"),[]) => Ok(Combinator::Exec),
+            (Some("DIP"),[]) => Ok(Combinator::Dip),
+            (Some("KEEP"),[]) => Ok(Combinator::Keep),
+            (Some("BI"),[]) => Ok(Combinator::Bi),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Combinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Combinator::Exec => write!(f,"exec"),
+            Combinator::Dip => write!(f,"dip"),
+            Combinator::Keep => write!(f,"keep"),
+            Combinator::Bi => write!(f,"bi")
+        }
+    }
+}
+
+impl Command for Combinator {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, mut stdin: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
+        let needed = match self {
+            Combinator::Exec => 1,
+            Combinator::Dip | Combinator::Keep => 2,
+            Combinator::Bi => 3
+        };
+        if stack.len() < needed {
+            return Err(Error::StackEmpty(stack.len(), needed));
+        }
+        macro_rules! run { ($chain:expr) => {
+            $chain.comm(stack, ctx, Box::new(&mut stdin) as Box<dyn std::io::Read>, Box::new(&mut stdout) as Box<dyn std::io::Write>)?
+        } }
+        Ok(match self {
+            Combinator::Exec => run!(pop_quote(stack)?),
+            Combinator::Dip => {
+                let quote = pop_quote(stack)?;
+                let v = stack.pop().unwrap();
+                let out = run!(quote);
+                stack.push(v);
+                out
+            },
+            Combinator::Keep => {
+                let quote = pop_quote(stack)?;
+                let v = stack.last().cloned().unwrap();
+                let out = run!(quote);
+                stack.push(v);
+                out
+            },
+            Combinator::Bi => {
+                let below = &stack[stack.len() - 2];
+                if !matches!(below, Value::Quote(_)) {
+                    return Err(Error::Type("quotation",below.kind()));
+                }
+                let second = pop_quote(stack)?;
+                let first = pop_quote(stack)?;
+                let v = stack.last().cloned().unwrap();
+                run!(first);
+                stack.push(v);
+                run!(second)
+            }
+        })
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Include(pub String);
+
+impl CommandDesc for Include {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "Include \"<Path>\"";
+    const DESCRIPTION: &'static str = "Runs another script file on the current stack, so the words and registers it defines become available. The path is relative to the including file, or to the working directory in the REPL.";
+}
+
+impl Parse for Include {
+    const KEYWORDS: &'static [&'static str] = &["INCLUDE"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [ast::Atom::Str(path)] => Ok(Include(path.clone())),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for Include {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"include {}",lexer::quote_str(&self.0))
+    }
+}
+
+impl Command for Include {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, stdin: impl std::io::Read, stdout: impl std::io::Write) -> Result<Option<String>> {
+        let path = match ctx.files.last().and_then(|f| f.parent()) {
+            Some(dir) => dir.join(&self.0),
+            None => std::path::PathBuf::from(&self.0)
+        };
+        let name = path.display().to_string();
+        let canonical = std::fs::canonicalize(&path)?;
+        if ctx.files.contains(&canonical) {
+            let chain = ctx.files.iter().skip_while(|f| **f != canonical).chain(std::iter::once(&canonical))
+                .map(|f| f.display().to_string()).intersperse(" -> ".to_string()).collect::<String>();
+            return Err(Error::IncludeCycle(chain));
+        }
+        let source = std::fs::read_to_string(&path)?;
+        ctx.files.push(canonical);
+        let out = Chain::from_bare(&source).and_then(|chain| chain.comm(stack,ctx,stdin,stdout));
+        ctx.files.pop();
+        out.map_err(|e| {
+            let e = e.with_source(&name,&source);
+            Error::Included(name,Box::new(e))
+        })
+    }
+}
+
+
+/// Indents every line of printed source one level, except lines continuing a block comment,
+/// whose text would change otherwise.
+fn indent(source: &str) -> String {
+    let comments = lexer::Lexer::new(source).filter_map(|t| match t {
+        Ok((lexer::Token::Comment(ast::Comment::Block(..)),span)) => Some(span),
+        _ => None
+    }).collect::<Vec<_>>();
+    let mut start = 0;
+    source.split('\n').map(|line| {
+        let continues_comment = comments.iter().any(|c| c.start < start && start < c.end);
+        start += line.len() + 1;
+        if line.is_empty() || continues_comment { line.to_string() } else { format!("    {line}") }
+    }).intersperse("\n".to_string()).collect()
+}
+
+/// Formats a number for the stack view, with nine decimals and its digits grouped in threes.
+pub fn format_num<N: number::Number>(input: &N) -> String {
+    let input = input.to_f64();
+    let sign = if input.is_sign_positive() { " " } else {"-"};
+    if input.is_finite() {
+        format!("{sign}{}",group_digits(&format!("{:.9}",input.abs())))
     } else {
         if input.is_nan() {
             format!("{sign}NaN")
@@ -777,4 +1304,21 @@ pub fn format_num(input: f64) -> String {
             format!("{sign}Inf")
         }
     }
-}
\ No newline at end of file
+}
+
+/// Groups the digits of an unsigned decimal in threes on both sides of the point. The integer
+/// part is padded on the left to a whole group, so that numbers stacked in a column line up.
+pub fn group_digits(num: &str) -> String {
+    let (int,frac) = match num.split_once('.') {
+        Some((int,frac)) => (int,Some(frac)),
+        None => (num,None)
+    };
+    let padded = " ".repeat((3 - int.len() % 3) % 3) + int;
+    let group = |digits: &str| digits.as_bytes().chunks(3)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .intersperse(" ".to_string()).collect::<String>();
+    match frac {
+        Some(frac) => format!("{}.{}",group(&padded),group(frac)),
+        None => group(&padded)
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops::{Add,Sub,Mul,Div};
+use crate::error::*;
+
+/// Numbers the stack can hold, so the same commands and operations can run on different numeric
+/// backends, chosen when the interpreter starts.
+///
+/// Only conversions from and to `f64` are required. Every other operation defaults to going
+/// through `f64`, so a backend only overrides the ones it can do better, like exact arithmetic.
+pub trait Number: Clone + PartialEq + PartialOrd + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + 'static {
+    /// Converts the result of a computation, which may already be rounded.
+    fn from_f64(n: f64) -> Self;
+    fn to_f64(&self) -> f64;
+
+    /// Converts a float standing for its shortest decimal form, like the factor of a unit. Exact
+    /// backends read it back from that form, so that `0.1` is exactly a tenth.
+    fn from_literal(n: f64) -> Self {
+        Self::from_f64(n)
+    }
+
+    /// Converts a fraction of two integers, written as `1/3`.
+    fn from_ratio(num: f64, den: f64) -> Self {
+        Self::from_f64(num / den)
+    }
+
+    /// Converts a number literal from its text. Backends more precise than floats read every
+    /// digit of it, where going through [`Number::from_literal`] would round them.
+    fn from_source(text: &str) -> Self {
+        Self::from_literal(text.parse().unwrap_or(f64::NAN))
+    }
+
+    /// Converts a fraction literal from the text of its integers.
+    fn from_ratio_source(num: &str, den: &str) -> Self {
+        Self::from_ratio(num.parse().unwrap_or(f64::NAN),den.parse().unwrap_or(f64::NAN))
+    }
+
+    /// Converts a complex number written as `3+4i` or `(3,4)`, if the backend can hold it. Real
+    /// backends only hold those without an imaginary part.
+    fn from_parts(re: f64, im: f64) -> Option<Self> {
+        (im == 0.0).then(|| Self::from_literal(re))
+    }
+
+    /// Converts a number with a radius written as `1.2±0.05`, as a range or as a standard
+    /// deviation, if the backend can hold it. Backends of exact numbers only hold those with a
+    /// radius of zero.
+    fn from_plus_minus(mid: f64, radius: f64) -> Option<Self> {
+        (radius == 0.0).then(|| Self::from_literal(mid))
+    }
+
+    /// The number literal that reads back as exactly this number, if there is one.
+    fn to_literal(&self) -> Option<f64> {
+        Some(self.to_f64())
+    }
+
+    /// Reads a number typed by the user, as `input` does.
+    fn parse(s: &str) -> Result<Self> {
+        Ok(Self::from_literal(s.parse::<f64>()?))
+    }
+
+    fn is_zero(&self) -> bool {
+        self.to_f64() == 0.0
+    }
+
+    /// Text shown for the number on the stack and by `print`.
+    fn format(&self) -> String {
+        crate::format_num(self)
+    }
+
+    fn log10(self) -> Self { Self::from_f64(self.to_f64().log10()) }
+    fn log2(self) -> Self { Self::from_f64(self.to_f64().log2()) }
+    fn ln(self) -> Self { Self::from_f64(self.to_f64().ln()) }
+    fn sqrt(self) -> Self { Self::from_f64(self.to_f64().sqrt()) }
+    fn powf(self, exp: Self) -> Self { Self::from_f64(self.to_f64().powf(exp.to_f64())) }
+    fn log(self, base: Self) -> Self { Self::from_f64(self.to_f64().log(base.to_f64())) }
+    fn recip(self) -> Self { Self::from_f64(self.to_f64().recip()) }
+    fn sin(self) -> Self { Self::from_f64(self.to_f64().sin()) }
+    fn cos(self) -> Self { Self::from_f64(self.to_f64().cos()) }
+    fn tan(self) -> Self { Self::from_f64(self.to_f64().tan()) }
+    fn asin(self) -> Self { Self::from_f64(self.to_f64().asin()) }
+    fn acos(self) -> Self { Self::from_f64(self.to_f64().acos()) }
+    fn atan(self) -> Self { Self::from_f64(self.to_f64().atan()) }
+
+    /// Real part, the number itself unless it is complex.
+    fn re(self) -> Self { self }
+    /// Imaginary part, zero unless the number is complex.
+    fn im(self) -> Self { Self::from_f64(0.0) }
+    fn conj(self) -> Self { self }
+    /// Angle from the positive real axis, which is pi for negative real numbers.
+    fn arg(self) -> Self { Self::from_f64(0.0_f64.atan2(self.to_f64())) }
+    fn abs(self) -> Self {
+        let zero = Self::from_f64(0.0);
+        if self < zero { zero - self } else { self }
+    }
+    /// The number with magnitude `r` at the angle `theta`. Real backends only have those at an
+    /// angle of zero, and of pi or minus pi as given by `pi` and `arg`, giving NaN for any other.
+    fn from_polar(r: Self, theta: Self) -> Self {
+        let theta = theta.to_f64();
+        if theta == 0.0 {
+            r
+        } else if theta.abs() == std::f64::consts::PI {
+            Self::from_f64(0.0) - r
+        } else {
+            Self::from_f64(f64::NAN)
+        }
+    }
+}
+
+impl Number for f64 {
+    fn from_f64(n: f64) -> Self {
+        n
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+}
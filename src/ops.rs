@@ -1,7 +1,14 @@
 use crate::error::*;
-use std::str::FromStr;
+use crate::ast;
 use rpncalc_macros::{SimpleOp,simple_op};
-use super::CommandDesc;
+use crate::context::Context;
+use crate::value::Value;
+use crate::number::Number;
+use super::{CommandDesc,Parse};
+use crate::check::Arity;
+use crate::units::{Unit,Dimensional};
+use crate::array::{Array,Shape,array_of};
+use crate::time::{Temporal,Dated,date_of};
 
 macro_rules! op_enum {
 	{
@@ -10,43 +17,53 @@ macro_rules! op_enum {
 		}
 	} => {
         #[derive(Clone)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 		pub enum OpEnum {
 			$($v($v),)*
 		}
-        #[allow(trivial_bounds)]
-		impl FromStr for OpEnum  where
-			$(
-				$v: FromStr,
-				<$v as FromStr>::Err: Into<Error>,
-			)*
-		{
-			type Err = Error;
-            #[allow(unused_mut,unused_variables)]
-			fn from_str(input: &str) -> Result<OpEnum> {
-				let mut parse_errors: Vec<Error> = vec![];
-				let trimmed_input = input.trim();
-				$(
-					match trimmed_input.parse::<$v>() {
-						Err(e) => parse_errors.push(e.into()),
-						Ok(v) => return Ok(OpEnum::$v(v))
-					}
-				)*
-				return Err(Error::ParseEnum(parse_errors))
-			}
-		}
+        impl OpEnum {
+            /// Parses the statement as the operation that owns `keyword`, if any does.
+            pub fn parse_keyword(keyword: &str, stmt: &ast::Stmt) -> Option<Result<OpEnum>> {
+                $(
+                    if <$v as Parse>::KEYWORDS.contains(&keyword) {
+                        return Some(<$v as Parse>::parse(stmt).map(OpEnum::$v));
+                    }
+                )*
+                None
+            }
+
+            pub fn has_keyword(keyword: &str) -> bool {
+                false $(|| <$v as Parse>::KEYWORDS.contains(&keyword))*
+            }
+
+            /// Values the operation pops, and values it pushes in their place.
+            pub fn arity(&self) -> (usize,usize) {
+                match self {
+                    $(OpEnum::$v(_) => <$v as Arity>::ARITY,)*
+                }
+            }
+        }
+
+        impl std::fmt::Display for OpEnum {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(OpEnum::$v(curr_op) => write!(f,"{curr_op}"),)*
+                }
+            }
+        }
 
         impl super::Command for OpEnum {
             #[allow(unused_variables)]
-            fn comm(self, stack: &mut Vec<f64>, stdin: impl ::std::io::Read, stdout: impl ::std::io::Write) -> Result<Option<String>> {
+            fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, stdin: impl ::std::io::Read, stdout: impl ::std::io::Write) -> Result<Option<String>> {
                 match self {
                     $(OpEnum::$v(curr_op) => {
-                        curr_op.comm(stack,stdin,stdout)
+                        curr_op.comm(stack,ctx,stdin,stdout)
                     },)*
                 }
             }
         }
 
-        pub const OP_NAMES_DESCRIPTIONS: [(Option<&'static str>,&'static str,&'static str);${count(v)}] = [$(
+        pub const OP_NAMES_DESCRIPTIONS: [(Option<&'static str>,&'static str,&'static str);${count($v)}] = [$(
             (<$v as CommandDesc>::SHORT_NAME,<$v as CommandDesc>::NAME,<$v as CommandDesc>::DESCRIPTION),
         )*];
 	}
@@ -54,24 +71,35 @@ macro_rules! op_enum {
 
 op_enum!{
     pub enum OpEnum {
-        InsNum, Arith, Constants, ExponentialsUnary, ExponentialsBinary, Trigonometric, Cmp, NOP
+        InsNum, InsFrac, InsComplex, InsPlusMinus, InsQuantity, InsTime, InsArray, Arith, Constants, ExponentialsUnary, ExponentialsBinary, Trigonometric, ComplexParts, Polar, To, LinAlgUnary, LinAlgBinary, DatesUnary, AddMonths, Cmp, NOP
     }
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct NOP;
 
-impl FromStr for NOP {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.trim() == "" {
+impl Parse for NOP {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        if stmt.0.is_empty() {
             Ok(NOP)
         } else {
-            Err(Error::ParseToken(Self::NAME))
+            Err(Error::BadArgs(Self::NAME))
         }
     }
 }
 
+impl std::fmt::Display for NOP {
+    fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Ok(())
+    }
+}
+
+impl Arity for NOP {
+    const ARITY: (usize,usize) = (0,0);
+}
+
 impl CommandDesc for NOP {
     const SHORT_NAME: Option<&'static str> = None;
     const NAME: &'static str = "<Empty>";
@@ -79,13 +107,14 @@ impl CommandDesc for NOP {
 }
 
 impl super::Command for NOP {
-    fn comm(self, _: &mut Vec<f64>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+    fn comm<N: Number>(self, _: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
         Ok(None)
     }
 }
 
 #[derive(Clone)]
-pub struct InsNum(pub f64);
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct InsNum(pub String);
 
 impl CommandDesc for InsNum {
     const SHORT_NAME: Option<&'static str> = None;
@@ -93,27 +122,341 @@ impl CommandDesc for InsNum {
     const DESCRIPTION: &'static str = "Just entering a Floating Point number will add it to the bottom of the stack";
 }
 
-impl FromStr for InsNum {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self> {
-        Ok(s.trim().parse::<f64>().map(|v| InsNum(v))?)
+impl Parse for InsNum {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::Num(_,text)] => Ok(InsNum(text.clone())),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for InsNum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}",crate::lexer::literal_source(&self.0))
     }
 }
 
+impl Arity for InsNum {
+    const ARITY: (usize,usize) = (0,1);
+}
+
 impl super::Command for InsNum {
-    
-    fn comm(self, stack: &mut Vec<f64>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
-        stack.push(self.0);
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        stack.push(Value::Num(N::from_source(&self.0)));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct InsFrac(pub String,pub String);
+
+impl CommandDesc for InsFrac {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "<Fraction>";
+    const DESCRIPTION: &'static str = "Entering two integers around a slash, like 1/3, will add their fraction to the bottom of the stack, which stays exact in rational mode";
+}
+
+impl Parse for InsFrac {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::Frac(num,den)] => Ok(InsFrac(num.clone(),den.clone())),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for InsFrac {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}/{}",self.0,self.1)
+    }
+}
+
+impl Arity for InsFrac {
+    const ARITY: (usize,usize) = (0,1);
+}
+
+impl super::Command for InsFrac {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        stack.push(Value::Num(N::from_ratio_source(&self.0,&self.1)));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct InsComplex(pub f64,pub f64);
+
+impl CommandDesc for InsComplex {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "<Complex>";
+    const DESCRIPTION: &'static str = "Entering a complex number, like 3+4i or (3,4), will add it to the bottom of the stack, in complex mode";
+}
+
+impl Parse for InsComplex {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::Complex(re,im)] => Ok(InsComplex(*re,*im)),
+            [ast::Atom::Group(group)] => match group.as_slice() {
+                [ast::Atom::Num(re,_),ast::Atom::Num(im,_)] => Ok(InsComplex(*re,*im)),
+                _ => Err(Error::BadArgs(Self::NAME))
+            },
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for InsComplex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}",crate::lexer::complex_source(self.0,self.1))
+    }
+}
+
+impl Arity for InsComplex {
+    const ARITY: (usize,usize) = (0,1);
+}
+
+impl super::Command for InsComplex {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        stack.push(Value::Num(N::from_parts(self.0,self.1).ok_or(Error::Complex)?));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct InsPlusMinus(pub f64,pub f64);
+
+impl CommandDesc for InsPlusMinus {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "<PlusMinus>";
+    const DESCRIPTION: &'static str = "Entering a number with a radius, like 1.2±0.05 or 1.2+-0.05, will add the range of numbers it covers to the bottom of the stack in interval mode, and a measurement with that standard deviation in uncertainty mode";
+}
+
+impl Parse for InsPlusMinus {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::PlusMinus(mid,radius)] => Ok(InsPlusMinus(*mid,*radius)),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for InsPlusMinus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}",crate::lexer::plus_minus_source(self.0,self.1))
+    }
+}
+
+impl Arity for InsPlusMinus {
+    const ARITY: (usize,usize) = (0,1);
+}
+
+impl super::Command for InsPlusMinus {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        stack.push(Value::Num(N::from_plus_minus(self.0,self.1).ok_or(Error::PlusMinus)?));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct InsQuantity(pub f64,pub Unit);
+
+impl CommandDesc for InsQuantity {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "<Quantity>";
+    const DESCRIPTION: &'static str = "Entering a number followed by a unit, like 9.81 m/s^2, will add it to the bottom of the stack with that unit";
+}
+
+impl Parse for InsQuantity {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::Num(n,_),ast::Atom::Word(unit)] => Ok(InsQuantity(*n,Unit::parse(unit)?)),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for InsQuantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{} {}",crate::lexer::num_source(self.0),self.1)
+    }
+}
+
+impl Arity for InsQuantity {
+    const ARITY: (usize,usize) = (0,1);
+}
+
+impl super::Command for InsQuantity {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        stack.push(crate::units::quantity(N::from_literal(self.0),Some(self.1)));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct To(pub Unit);
+
+impl CommandDesc for To {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "To";
+    const DESCRIPTION: &'static str = "Converts the quantity at the bottom of the stack to another unit of the same dimension, as in to km";
+}
+
+impl Parse for To {
+    const KEYWORDS: &'static [&'static str] = &["TO"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [ast::Atom::Word(unit)] => Ok(To(Unit::parse(unit)?)),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for To {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"to {}",self.0)
+    }
+}
+
+impl Arity for To {
+    const ARITY: (usize,usize) = (1,1);
+}
+
+impl super::Command for To {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        let v = stack.last().ok_or(Error::StackEmpty(0,1))?;
+        let unit = v.unit().ok_or_else(|| Error::Units("no unit".to_string(),self.0.to_string()))?;
+        let n = unit.convert(v.as_quantity()?,&self.0)?;
+        stack.pop();
+        stack.push(Value::Quantity(n,self.0));
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct InsTime(pub crate::time::Literal);
+
+impl CommandDesc for InsTime {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "<Time>";
+    const DESCRIPTION: &'static str = "Entering a date like 2026-10-17 or 2026-10-17T14:30 will add it to the bottom of the stack, and a time of day like 14:30 or a duration like 1h30m will add it as a quantity of time";
+}
+
+impl Parse for InsTime {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.0.as_slice() {
+            [ast::Atom::Time(time)] => Ok(InsTime(*time)),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for InsTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"{}",self.0)
+    }
+}
+
+impl Arity for InsTime {
+    const ARITY: (usize,usize) = (0,1);
+}
+
+impl super::Command for InsTime {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        stack.push(self.0.value());
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct InsArray(pub Shape,pub Vec<f64>);
+
+impl InsArray {
+    /// Reads a block holding only numbers, like `[1 2 3]`, as a vector, and a block holding only
+    /// such rows, like `[[1 2] [3 4]]`, as a matrix. Returns `None` for any other block, which
+    /// is a quotation, and for blocks of a single number, which are quotations too.
+    pub fn literal(stmt: &ast::Stmt) -> Option<Result<InsArray>> {
+        let [ast::Atom::Block(ast::Block(stmts))] = stmt.0.as_slice() else { return None };
+        let [row] = stmts.as_slice() else { return None };
+        let nums = |atoms: &[ast::Atom]| atoms.iter().map(|a| match a {
+            ast::Atom::Num(n,_) => Some(*n),
+            _ => None
+        }).collect::<Option<Vec<_>>>();
+        if let Some(data) = nums(&row.0).filter(|data| data.len() > 1) {
+            return Some(Ok(InsArray(Shape::Vector(data.len()),data)));
+        }
+        let rows = row.0.iter().map(|a| match a {
+            ast::Atom::Block(ast::Block(stmts)) => match stmts.as_slice() {
+                [row] => nums(&row.0).filter(|row| !row.is_empty()),
+                _ => None
+            },
+            _ => None
+        }).collect::<Option<Vec<_>>>()?;
+        let cols = rows.first()?.len();
+        if rows.len() * cols < 2 {
+            return None;
+        }
+        Some(match rows.iter().find(|row| row.len() != cols) {
+            Some(row) => Err(Error::Shape(format!("rows of {cols}"),row.len().to_string())),
+            None => Ok(InsArray(Shape::Matrix(rows.len(),cols),rows.concat()))
+        })
+    }
+}
+
+impl CommandDesc for InsArray {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "<Array>";
+    const DESCRIPTION: &'static str = "Entering numbers in square brackets, like [1 2 3], will add them to the bottom of the stack as a vector, and rows of them, like [[1 2] [3 4]], as a matrix";
+}
+
+impl Parse for InsArray {
+    const KEYWORDS: &'static [&'static str] = &[];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        InsArray::literal(stmt).unwrap_or(Err(Error::BadArgs(Self::NAME)))
+    }
+}
+
+impl std::fmt::Display for InsArray {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let row = |row: &[f64]| format!("[{}]",row.iter().map(|n| crate::lexer::num_source(*n)).intersperse(" ".to_string()).collect::<String>());
+        match self.0 {
+            Shape::Vector(_) => write!(f,"{}",row(&self.1)),
+            Shape::Matrix(_,cols) => write!(f,"[{}]",self.1.chunks(cols).map(row).intersperse(" ".to_string()).collect::<String>())
+        }
+    }
+}
+
+impl Arity for InsArray {
+    const ARITY: (usize,usize) = (0,1);
+}
+
+impl super::Command for InsArray {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        stack.push(Value::Array(Array { shape: self.0, data: self.1.into_iter().map(N::from_literal).collect() }));
         Ok(None)
     }
 }
 
 #[derive(Clone,PartialEq,SimpleOp)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 #[simple_op(
     name = "+ - * /",
-    description = "Basic Arithmetic operations",
+    description = "Basic Arithmetic operations, which also combine the units of quantities",
     input_arity = 2,
-    exclude_fromstr = true
+    exclude_parse = true,
+    dimensional = true
 )]
 pub enum Arith {
     #[simple_op(|lhs,rhs| lhs + rhs)]
@@ -126,104 +469,406 @@ pub enum Arith {
     Div
 }
 
-impl FromStr for Arith {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.trim() {
-            "+" => Ok(Arith::Add),
-            "-" => Ok(Arith::Sub),
-            "*" => Ok(Arith::Mul),
-            "/" => Ok(Arith::Div),
-            _ => Err(Error::ParseToken(Self::NAME))
+impl Parse for Arith {
+    const KEYWORDS: &'static [&'static str] = &["+","-","*","/"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match (stmt.keyword().as_deref(),stmt.args()) {
+            (Some("+"),[]) => Ok(Arith::Add),
+            (Some("-"),[]) => Ok(Arith::Sub),
+            (Some("*"),[]) => Ok(Arith::Mul),
+            (Some("/"),[]) => Ok(Arith::Div),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+/// Sums need inputs of the same dimension, while products and quotients multiply and divide
+/// their units.
+impl Dimensional for Arith {
+    fn units(&self, units: &[Option<&Unit>]) -> Result<(Vec<Option<Unit>>,Option<Unit>)> {
+        let [lhs,rhs] = units else { unreachable!("arithmetic takes two inputs") };
+        let or_none = |unit: Option<&Unit>| unit.cloned().unwrap_or(Unit(vec![]));
+        match (self,lhs.or(*rhs)) {
+            (Arith::Add | Arith::Sub,_) => crate::units::same_units(units),
+            (_,None) => Ok((vec![None,None],None)),
+            (Arith::Mul,_) => Ok((vec![None,None],Some(or_none(*lhs).mul(&or_none(*rhs))?))),
+            (Arith::Div,_) => Ok((vec![None,None],Some(or_none(*lhs).mul(&or_none(*rhs).recip())?)))
+        }
+    }
+}
+
+/// A duration can be added to a date or taken from it, and dates taken from each other.
+impl Temporal for Arith {
+    fn dates(&self, kinds: &[&'static str]) -> Result<Dated> {
+        match (self,kinds) {
+            (Arith::Add,["date","quantity"] | ["quantity","date"]) | (Arith::Sub,["date","quantity"]) => Ok(Dated::Date),
+            (Arith::Sub,["date","date"]) => Ok(Dated::Elapsed),
+            (Arith::Add | Arith::Sub,["date",other] | [other,"date"]) => Err(Error::Type(if *self == Arith::Sub { "date" } else { "duration" },other)),
+            _ => Err(Error::Type("number","date"))
+        }
+    }
+}
+
+impl std::fmt::Display for Arith {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arith::Add => write!(f,"+"),
+            Arith::Sub => write!(f,"-"),
+            Arith::Mul => write!(f,"*"),
+            Arith::Div => write!(f,"/")
         }
     }
 }
 
 #[derive(Clone,PartialEq,SimpleOp)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 #[simple_op(
     name = "Pi | E | Inf",
     description = "Constants made available for use",
     input_arity = 0
 )]
 pub enum Constants {
-    #[simple_op(|| std::f64::consts::E)]
+    #[simple_op(|| N::from_f64(std::f64::consts::E))]
     E,
-    #[simple_op(|| std::f64::consts::PI)]
+    #[simple_op(|| N::from_f64(std::f64::consts::PI))]
     PI,
-    #[simple_op(|| std::f64::INFINITY)]
+    #[simple_op(|| N::from_f64(f64::INFINITY))]
     Inf
 }
 
 #[derive(Clone,PartialEq,SimpleOp)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 #[simple_op(
     name = "Log10 | Log2 | LogE | Root2",
     description = "Exponential operations that take one argument",
     input_arity = 1
 )]
 pub enum ExponentialsUnary {
-    #[simple_op(|input: f64| input.log10())]
+    #[simple_op(|input: N| input.log10())]
     Log10,
-    #[simple_op(|input: f64| input.log2())]
+    #[simple_op(|input: N| input.log2())]
     Log2,
-    #[simple_op(|input: f64| input.ln())]
+    #[simple_op(|input: N| input.ln())]
     LogE,
-    #[simple_op(|input: f64| input.sqrt())]
+    #[simple_op(|input: N| input.sqrt())]
     Root2
 }
 
 #[derive(Clone,PartialEq,SimpleOp)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 #[simple_op(
     name = "Pow | LogN | RootN",
     description = "Exponential operations that take two arguments",
     input_arity = 2
 )]
 pub enum ExponentialsBinary {
-    #[simple_op(|lhs: f64,rhs: f64| lhs.powf(rhs))]
+    #[simple_op(|lhs: N,rhs: N| lhs.powf(rhs))]
     Pow,
-    #[simple_op(|lhs: f64,rhs: f64| rhs.log(lhs))]
+    #[simple_op(|lhs: N,rhs: N| rhs.log(lhs))]
     LogN,
-    #[simple_op(|lhs: f64,rhs: f64| rhs.powf(lhs.recip()))]
+    #[simple_op(|lhs: N,rhs: N| rhs.powf(lhs.recip()))]
     RootN
 }
 
 #[derive(Clone,PartialEq,SimpleOp)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 #[simple_op(
     name = "Sin | Cos | Tan | ASin | ACos | ATan",
     description = "Forward and inverse trigonometric functions",
     input_arity = 1
 )]
 pub enum Trigonometric {
-    #[simple_op(|input: f64| input.sin())]
+    #[simple_op(|input: N| input.sin())]
     Sin,
-    #[simple_op(|input: f64| input.cos())]
+    #[simple_op(|input: N| input.cos())]
     Cos,
-    #[simple_op(|input: f64| input.tan())]
+    #[simple_op(|input: N| input.tan())]
     Tan,
-    #[simple_op(|input: f64| input.asin())]
+    #[simple_op(|input: N| input.asin())]
     ASin,
-    #[simple_op(|input: f64| input.acos())]
+    #[simple_op(|input: N| input.acos())]
     ACos,
-    #[simple_op(|input: f64| input.atan())]
+    #[simple_op(|input: N| input.atan())]
     ATan
 }
 
+#[derive(Clone,PartialEq,SimpleOp)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+#[simple_op(
+    name = "Re | Im | Conj | Arg | Abs",
+    description = "Real and imaginary parts, conjugate, angle and magnitude of a complex number",
+    input_arity = 1
+)]
+pub enum ComplexParts {
+    #[simple_op(|input: N| input.re())]
+    Re,
+    #[simple_op(|input: N| input.im())]
+    Im,
+    #[simple_op(|input: N| input.conj())]
+    Conj,
+    #[simple_op(|input: N| input.arg())]
+    Arg,
+    #[simple_op(|input: N| input.abs())]
+    Abs
+}
+
+#[derive(Clone,PartialEq,SimpleOp)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+#[simple_op(
+    name = "Polar",
+    description = "Complex number from its magnitude and its angle in radians, the reverse of Abs and Arg",
+    input_arity = 2
+)]
+pub enum Polar {
+    #[simple_op(|r: N,theta: N| N::from_polar(r,theta))]
+    Polar
+}
+
+#[derive(Clone,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum LinAlgUnary {
+    Transpose,
+    Det,
+    Inv
+}
+
+impl CommandDesc for LinAlgUnary {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "Transpose | Det | Inv";
+    const DESCRIPTION: &'static str = "Transpose, determinant and inverse of a matrix";
+}
+
+impl Parse for LinAlgUnary {
+    const KEYWORDS: &'static [&'static str] = &["TRANSPOSE","DET","INV"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match (stmt.keyword().as_deref(),stmt.args()) {
+            (Some("TRANSPOSE"),[]) => Ok(LinAlgUnary::Transpose),
+            (Some("DET"),[]) => Ok(LinAlgUnary::Det),
+            (Some("INV"),[]) => Ok(LinAlgUnary::Inv),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for LinAlgUnary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinAlgUnary::Transpose => write!(f,"transpose"),
+            LinAlgUnary::Det => write!(f,"det"),
+            LinAlgUnary::Inv => write!(f,"inv")
+        }
+    }
+}
+
+impl Arity for LinAlgUnary {
+    const ARITY: (usize,usize) = (1,1);
+}
+
+impl super::Command for LinAlgUnary {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        let a = array_of(stack.last().ok_or(Error::StackEmpty(0,1))?)?;
+        let out = match self {
+            LinAlgUnary::Transpose => Value::Array(a.clone().transpose()),
+            LinAlgUnary::Det => Value::Num(a.det()?),
+            LinAlgUnary::Inv => Value::Array(a.inv()?)
+        };
+        stack.pop();
+        stack.push(out);
+        Ok(None)
+    }
+}
+
+#[derive(Clone,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum LinAlgBinary {
+    Dot,
+    Cross,
+    Solve
+}
+
+impl CommandDesc for LinAlgBinary {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "Dot | Cross | Solve";
+    const DESCRIPTION: &'static str = "Dot or matrix product, cross product of two vectors of 3, and solution x of A x = b for a matrix A and a vector or matrix b";
+}
+
+impl Parse for LinAlgBinary {
+    const KEYWORDS: &'static [&'static str] = &["DOT","CROSS","SOLVE"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match (stmt.keyword().as_deref(),stmt.args()) {
+            (Some("DOT"),[]) => Ok(LinAlgBinary::Dot),
+            (Some("CROSS"),[]) => Ok(LinAlgBinary::Cross),
+            (Some("SOLVE"),[]) => Ok(LinAlgBinary::Solve),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for LinAlgBinary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinAlgBinary::Dot => write!(f,"dot"),
+            LinAlgBinary::Cross => write!(f,"cross"),
+            LinAlgBinary::Solve => write!(f,"solve")
+        }
+    }
+}
+
+impl Arity for LinAlgBinary {
+    const ARITY: (usize,usize) = (2,1);
+}
+
+impl super::Command for LinAlgBinary {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        let [lhs,rhs] = match stack.as_slice() {
+            [.., lhs, rhs] => [array_of(lhs)?,array_of(rhs)?],
+            _ => return Err(Error::StackEmpty(stack.len(),2))
+        };
+        let out = match self {
+            LinAlgBinary::Dot => lhs.dot(rhs)?,
+            LinAlgBinary::Cross => Value::Array(lhs.cross(rhs)?),
+            LinAlgBinary::Solve => Value::Array(lhs.solve(rhs)?)
+        };
+        stack.truncate(stack.len() - 2);
+        stack.push(out);
+        Ok(None)
+    }
+}
+
+#[derive(Clone,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum DatesUnary {
+    Weekday,
+    ToEpoch,
+    FromEpoch
+}
+
+impl CommandDesc for DatesUnary {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "Weekday | ToEpoch | FromEpoch";
+    const DESCRIPTION: &'static str = "Day of the week of a date, from 1 for Monday to 7 for Sunday, and conversions of a date to and from seconds since the Unix epoch";
+}
+
+impl Parse for DatesUnary {
+    const KEYWORDS: &'static [&'static str] = &["WEEKDAY","TOEPOCH","FROMEPOCH"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match (stmt.keyword().as_deref(),stmt.args()) {
+            (Some("WEEKDAY"),[]) => Ok(DatesUnary::Weekday),
+            (Some("TOEPOCH"),[]) => Ok(DatesUnary::ToEpoch),
+            (Some("FROMEPOCH"),[]) => Ok(DatesUnary::FromEpoch),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for DatesUnary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatesUnary::Weekday => write!(f,"weekday"),
+            DatesUnary::ToEpoch => write!(f,"toepoch"),
+            DatesUnary::FromEpoch => write!(f,"fromepoch")
+        }
+    }
+}
+
+impl Arity for DatesUnary {
+    const ARITY: (usize,usize) = (1,1);
+}
+
+impl super::Command for DatesUnary {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        let v = stack.last().ok_or(Error::StackEmpty(0,1))?;
+        let out = match self {
+            DatesUnary::Weekday => Value::Num(N::from_f64(crate::time::weekday(date_of(v)?.to_f64())?)),
+            DatesUnary::ToEpoch => Value::Num(date_of(v)?),
+            DatesUnary::FromEpoch => Value::Date(v.as_num()?)
+        };
+        stack.pop();
+        stack.push(out);
+        Ok(None)
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct AddMonths;
+
+impl CommandDesc for AddMonths {
+    const SHORT_NAME: Option<&'static str> = None;
+    const NAME: &'static str = "AddMonths";
+    const DESCRIPTION: &'static str = "Adds a whole number of months to a date, keeping its day unless the month is shorter, in which case it becomes the last day of the month";
+}
+
+impl Parse for AddMonths {
+    const KEYWORDS: &'static [&'static str] = &["ADDMONTHS"];
+    fn parse(stmt: &ast::Stmt) -> Result<Self> {
+        match stmt.args() {
+            [] => Ok(AddMonths),
+            _ => Err(Error::BadArgs(Self::NAME))
+        }
+    }
+}
+
+impl std::fmt::Display for AddMonths {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"addmonths")
+    }
+}
+
+impl Arity for AddMonths {
+    const ARITY: (usize,usize) = (2,1);
+}
+
+impl super::Command for AddMonths {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, _: &mut Context<N>, _: impl std::io::Read, _: impl std::io::Write) -> Result<Option<String>> {
+        let (date,months) = match stack.as_slice() {
+            [.., date, months] => (date_of(date)?,months.as_num()?.to_f64()),
+            _ => return Err(Error::StackEmpty(stack.len(),2))
+        };
+        let secs = crate::time::add_months(date.to_f64(),months)?;
+        stack.truncate(stack.len() - 2);
+        stack.push(Value::Date(N::from_f64(secs)));
+        Ok(None)
+    }
+}
+
 #[derive(Clone, PartialEq,SimpleOp)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 #[simple_op(
     name = "= | != | > | >= | < | <=",
     description = "Binary operators that compare two elements on the stack, the return 1 for true and 0 for false. Mostly for use with the if command.",
-    input_arity = 2
+    input_arity = 2,
+    dimensional = true
 )]
 pub enum Cmp {
-    #[simple_op(|lhs: f64,rhs: f64| if lhs == rhs { 1.0 } else { 0.0 })]
+    #[simple_op(|lhs: N,rhs: N| if lhs == rhs { N::from_f64(1.0) } else { N::from_f64(0.0) })]
     Eq,
-    #[simple_op(|lhs: f64,rhs: f64| if lhs != rhs { 1.0 } else { 0.0 })]
+    #[simple_op(|lhs: N,rhs: N| if lhs != rhs { N::from_f64(1.0) } else { N::from_f64(0.0) })]
     Neq,
-    #[simple_op(|lhs: f64,rhs: f64| if lhs >  rhs { 1.0 } else { 0.0 })]
+    #[simple_op(|lhs: N,rhs: N| if lhs >  rhs { N::from_f64(1.0) } else { N::from_f64(0.0) })]
     Gt,
-    #[simple_op(|lhs: f64,rhs: f64| if lhs >= rhs { 1.0 } else { 0.0 })]
+    #[simple_op(|lhs: N,rhs: N| if lhs >= rhs { N::from_f64(1.0) } else { N::from_f64(0.0) })]
     Gte,
-    #[simple_op(|lhs: f64,rhs: f64| if lhs <  rhs { 1.0 } else { 0.0 })]
+    #[simple_op(|lhs: N,rhs: N| if lhs <  rhs { N::from_f64(1.0) } else { N::from_f64(0.0) })]
     Lt,
-    #[simple_op(|lhs: f64,rhs: f64| if lhs <= rhs { 1.0 } else { 0.0 })]
+    #[simple_op(|lhs: N,rhs: N| if lhs <= rhs { N::from_f64(1.0) } else { N::from_f64(0.0) })]
     Lte
 }
+
+/// Quantities are compared in the unit of the first one, and only with quantities of the same
+/// dimension.
+impl Dimensional for Cmp {
+    fn units(&self, units: &[Option<&Unit>]) -> Result<(Vec<Option<Unit>>,Option<Unit>)> {
+        crate::units::same_units(units).map(|(into,_)| (into,None))
+    }
+}
+
+impl Temporal for Cmp {
+    fn dates(&self, kinds: &[&'static str]) -> Result<Dated> {
+        match kinds {
+            ["date","date"] => Ok(Dated::Plain),
+            ["date",other] | [other,_] => Err(Error::Type("date",other)),
+            _ => unreachable!("comparisons take two inputs")
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::context::Context;
+use crate::value::Value;
+use crate::number::Number;
+use crate::ops::{OpEnum,InsNum,InsFrac,InsComplex,InsPlusMinus,NOP};
+use crate::{Chain,CommandOrOp,CommandEnum,Command,Conditional,ConditionalKind,Repeat,Register,Def,Quote};
+
+/// Simplifies a chain without changing what it does: operations whose inputs are all numbers
+/// written right before them are computed ahead of time, an `if` right after a number is
+/// replaced by the branch it would take, and empty statements are removed.
+///
+/// Nothing is moved past anything else, so commands with side effects like `input`, `print`
+/// and `display` run in the same order and on the same stack as before. Results are computed
+/// with the backend `N`, and only folded when a number literal reads back as exactly the result.
+pub fn optimize<N: Number>(chain: Chain) -> Chain {
+    let mut out = vec![];
+    for c in chain.0 {
+        push::<N>(&mut out,c);
+    }
+    let last = out.len().saturating_sub(1);
+    Chain(out.into_iter().enumerate().filter(|(i,c)| *i == last || !is_nop(c)).map(|(_,c)| c).collect())
+}
+
+fn is_nop(c: &CommandOrOp) -> bool {
+    matches!(c, CommandOrOp::Op(OpEnum::NOP(_),_,_))
+}
+
+/// Value of a number, fraction, complex or radius literal, if the backend can hold it.
+fn literal<N: Number>(c: &CommandOrOp) -> Option<N> {
+    match c {
+        CommandOrOp::Op(OpEnum::InsNum(InsNum(text)),_,_) => Some(N::from_source(text)),
+        CommandOrOp::Op(OpEnum::InsFrac(InsFrac(num,den)),_,_) => Some(N::from_ratio_source(num,den)),
+        CommandOrOp::Op(OpEnum::InsComplex(InsComplex(re,im)),_,_) => N::from_parts(*re,*im),
+        CommandOrOp::Op(OpEnum::InsPlusMinus(InsPlusMinus(mid,radius)),_,_) => N::from_plus_minus(*mid,*radius),
+        _ => None
+    }
+}
+
+/// Whether the command never has any output, in which case an empty statement after it does
+/// not change the output of its chain either.
+fn is_quiet(c: &CommandOrOp) -> bool {
+    match c {
+        CommandOrOp::Op(_,_,_) => true,
+        CommandOrOp::Command(c,_,_) => matches!(c,
+            CommandEnum::Drop(_) | CommandEnum::Dup(_) | CommandEnum::Swap(_) | CommandEnum::Reverse(_) | CommandEnum::Pack(_) | CommandEnum::Def(_) |
+            CommandEnum::Quote(_) | CommandEnum::Input(_) | CommandEnum::Register(Register::Store(_) | Register::Recall(_) | Register::Purge(_))
+        )
+    }
+}
+
+/// Appends a command to an optimized chain, folding it into what precedes it when possible.
+fn push<N: Number>(out: &mut Vec<CommandOrOp>, c: CommandOrOp) {
+    match inner::<N>(c) {
+        c if is_nop(&c) => if out.last().is_some_and(|last| !is_quiet(last)) {
+            out.push(c);
+        },
+        CommandOrOp::Command(CommandEnum::Chain(chain),_,_) => for c in optimize::<N>(chain).0 {
+            push::<N>(out,c);
+        },
+        CommandOrOp::Command(CommandEnum::Conditional(Conditional(ConditionalKind::If,[first,second])),span,comments) => match out.last().and_then(literal::<N>) {
+            Some(n) => {
+                // The branch keeps the comments of the condition and the `if`, and an empty one
+                // still ends the chain with no output.
+                let condition = out.pop().unwrap();
+                let comments = condition.comments().iter().cloned().chain(comments).collect::<Vec<_>>();
+                let mut branch = if n.is_zero() { first } else { second }.0;
+                match branch.first_mut() {
+                    Some(CommandOrOp::Command(_,_,first) | CommandOrOp::Op(_,_,first)) => { first.splice(0..0,comments); },
+                    None => branch.push(CommandOrOp::Op(OpEnum::NOP(NOP),condition.span().to(span),comments))
+                }
+                for c in branch {
+                    push::<N>(out,c);
+                }
+            },
+            None => {
+                out.push(CommandOrOp::Command(CommandEnum::Conditional(Conditional(ConditionalKind::If,[first,second])),span,comments));
+            }
+        },
+        CommandOrOp::Op(o,span,comments) if !matches!(o, OpEnum::InsNum(_) | OpEnum::InsFrac(_) | OpEnum::InsComplex(_) | OpEnum::InsPlusMinus(_) | OpEnum::InsQuantity(_) | OpEnum::InsTime(_) | OpEnum::InsArray(_) | OpEnum::NOP(_)) => {
+            let (pops,_) = o.arity();
+            let nums = out.iter().rev().take_while(|c| literal::<N>(c).is_some()).count();
+            if pops > nums {
+                out.push(CommandOrOp::Op(o,span,comments));
+                return;
+            }
+            let args = out.split_off(out.len() - pops);
+            let mut stack = args.iter().filter_map(literal::<N>).map(Value::Num).collect::<Vec<_>>();
+            let folded = match (o.clone().comm(&mut stack,&mut Context::default(),std::io::empty(),std::io::sink()),stack.as_slice()) {
+                (Ok(_),[Value::Num(n)]) => n.to_literal(),
+                _ => None
+            };
+            match folded {
+                Some(n) => {
+                    let span = args.first().map_or(span,|first| first.span().to(span));
+                    let comments = args.iter().flat_map(|c| c.comments().iter().cloned()).chain(comments).collect();
+                    out.push(CommandOrOp::Op(OpEnum::InsNum(InsNum(crate::lexer::num_source(n))),span,comments));
+                },
+                None => {
+                    out.extend(args);
+                    out.push(CommandOrOp::Op(o,span,comments));
+                }
+            }
+        },
+        c => out.push(c)
+    }
+}
+
+/// Optimizes the chains nested in a command.
+fn inner<N: Number>(c: CommandOrOp) -> CommandOrOp {
+    let (c,span,comments) = match c {
+        CommandOrOp::Command(c,span,comments) => (c,span,comments),
+        op => return op
+    };
+    let c = match c {
+        CommandEnum::Def(Def(name,body)) => CommandEnum::Def(Def(name,optimize::<N>(body))),
+        CommandEnum::Quote(Quote(chain)) => CommandEnum::Quote(Quote(optimize::<N>(chain))),
+        CommandEnum::Conditional(Conditional(kind,[first,second])) => CommandEnum::Conditional(Conditional(kind,[optimize::<N>(first),optimize::<N>(second)])),
+        CommandEnum::Repeat(repeat) => CommandEnum::Repeat(match repeat {
+            Repeat::Bounded(n,body) => Repeat::Bounded(n,Box::new(body_of::<N>(*body))),
+            Repeat::Unbounded(body) => Repeat::Unbounded(Box::new(body_of::<N>(*body))),
+            Repeat::While([cond,body]) => Repeat::While([optimize::<N>(cond),optimize::<N>(body)]),
+            Repeat::For(bounds,register,body) => Repeat::For(bounds,register,optimize::<N>(body))
+        }),
+        c => c
+    };
+    CommandOrOp::Command(c,span,comments)
+}
+
+/// Optimizes the body of `repeat`, which is a chain or a single command.
+fn body_of<N: Number>(body: CommandOrOp) -> CommandOrOp {
+    match body {
+        CommandOrOp::Command(CommandEnum::Chain(chain),span,comments) => CommandOrOp::Command(CommandEnum::Chain(optimize::<N>(chain)),span,comments),
+        body => match optimize::<N>(Chain(vec![body.clone()])).0.as_slice() {
+            [single] => single.clone(),
+            _ => body
+        }
+    }
+}
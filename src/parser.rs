@@ -0,0 +1,152 @@
+use crate::error::*;
+use crate::lexer::{Lexer,Token};
+use crate::span::Span;
+use crate::ast::*;
+
+/// Recursive descent parser for the following grammar, where comments may appear between any
+/// two tokens and are kept on the statement they appear in, or on the one before when they
+/// follow its semicolon on the same line:
+///
+/// ```text
+/// program := stmts EOF
+/// stmts   := stmt ( ";" stmt )*
+/// stmt    := atom*
+/// atom    := NUM | WORD | STR | block | group
+/// block   := "[" stmts "]"
+/// group   := "(" ( atom ( "," atom )* )? ")"
+/// ```
+pub struct Parser<'a> {
+    tokens: std::iter::Peekable<Lexer<'a>>,
+    /// End of the last consumed token, where an empty statement is located
+    pos: usize,
+    input: &'a str,
+    /// Comments skipped so far that are not yet attached to a statement
+    comments: Vec<Comment>
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Parser { tokens: Lexer::new(input).peekable(), pos: 0, input, comments: vec![] }
+    }
+
+    fn skip_comments(&mut self) {
+        while let Some(Ok((Token::Comment(_),_))) = self.tokens.peek() {
+            if let Some(Ok((Token::Comment(c),_))) = self.tokens.next() {
+                self.comments.push(c);
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token>> {
+        self.skip_comments();
+        if let Some(Err(_)) = self.tokens.peek() {
+            return self.next().map(|_| None);
+        }
+        Ok(self.tokens.peek().and_then(|t| t.as_ref().ok()).map(|(t,_)| t))
+    }
+
+    fn next(&mut self) -> Result<Option<(Token,Span)>> {
+        self.skip_comments();
+        let next = self.tokens.next().transpose()?;
+        if let Some((_,span)) = &next {
+            self.pos = span.end;
+        }
+        Ok(next)
+    }
+
+    fn unexpected(&self, expected: &'static str, found: Option<(Token,Span)>) -> Error {
+        match found {
+            Some((t,span)) => Error::Expected(expected,t.to_string()).at(span),
+            None => Error::Expected(expected,"end of input".to_string()).at(Span::new(self.input.len(),self.input.len()))
+        }
+    }
+
+    pub fn program(&mut self) -> Result<Program> {
+        let stmts = self.stmts()?;
+        match self.next()? {
+            None => Ok(Program(stmts)),
+            Some((Token::RBracket,span)) => Err(Error::UnbBraces.at(span)),
+            found => Err(self.unexpected("';' or end of input",found))
+        }
+    }
+
+    fn stmts(&mut self) -> Result<Vec<Stmt>> {
+        let mut stmts = vec![self.stmt(self.comments.len())?];
+        while let Some(Token::Semi) = self.peek()? {
+            self.next()?;
+            let first_comment = self.comments.len();
+            self.trailing_comments(stmts.last_mut().expect("there is at least one statement"));
+            stmts.push(self.stmt(first_comment)?);
+        }
+        Ok(stmts)
+    }
+
+    /// Moves the comments filling the rest of the line after a semicolon to the statement it
+    /// ends, so that `1; # one` stays with the `1` rather than the statement on the next line.
+    fn trailing_comments(&mut self, stmt: &mut Stmt) {
+        let before = self.comments.len();
+        self.skip_comments();
+        let line_end = self.input[self.pos..].find('\n').map_or(self.input.len(),|i| self.pos + i);
+        let on_line = self.comments[before..].iter().take_while(|c| c.span().start < line_end).count();
+        let rest_blank = self.comments[before..before + on_line].last()
+            .is_some_and(|last| self.input[last.span().end.min(line_end)..line_end].trim().is_empty());
+        if rest_blank {
+            stmt.2.extend(self.comments.drain(before..before + on_line));
+        }
+    }
+
+    /// Parses a statement, which keeps the comments skipped from index `first_comment` on.
+    fn stmt(&mut self, first_comment: usize) -> Result<Stmt> {
+        let mut atoms = vec![];
+        let mut span = Span::new(self.pos,self.pos);
+        while let Some(t) = self.peek()? {
+            if matches!(t, Token::Semi | Token::RBracket | Token::RParen | Token::Comma) { break }
+            let (atom,atom_span) = self.atom()?;
+            span = if atoms.is_empty() { atom_span } else { span.to(atom_span) };
+            atoms.push(atom);
+        }
+        Ok(Stmt(atoms,span,self.comments.split_off(first_comment)))
+    }
+
+    fn atom(&mut self) -> Result<(Atom,Span)> {
+        match self.next()? {
+            Some((Token::Num(n,text),span)) => Ok((Atom::Num(n,text),span)),
+            Some((Token::Frac(n,d),span)) => Ok((Atom::Frac(n,d),span)),
+            Some((Token::Complex(re,im),span)) => Ok((Atom::Complex(re,im),span)),
+            Some((Token::PlusMinus(mid,radius),span)) => Ok((Atom::PlusMinus(mid,radius),span)),
+            Some((Token::Time(time),span)) => Ok((Atom::Time(time),span)),
+            Some((Token::Word(w),span)) => Ok((Atom::Word(w),span)),
+            Some((Token::Str(s),span)) => Ok((Atom::Str(s),span)),
+            Some((Token::LBracket,open)) => {
+                let stmts = self.stmts()?;
+                match self.next()? {
+                    Some((Token::RBracket,close)) => Ok((Atom::Block(Block(stmts)),open.to(close))),
+                    None => Err(Error::UnbBraces.at(open)),
+                    found => Err(self.unexpected("';' or ']'",found))
+                }
+            },
+            Some((Token::LParen,open)) => self.group(open),
+            found => Err(self.unexpected("an atom",found))
+        }
+    }
+
+    fn group(&mut self, open: Span) -> Result<(Atom,Span)> {
+        let mut atoms = vec![];
+        if let Some(Token::RParen) = self.peek()? {
+            self.next()?;
+            return Ok((Atom::Group(atoms),open.to(Span::new(self.pos,self.pos))));
+        }
+        loop {
+            atoms.push(self.atom()?.0);
+            match self.next()? {
+                Some((Token::Comma,_)) => (),
+                Some((Token::RParen,close)) => break Ok((Atom::Group(atoms),open.to(close))),
+                found => break Err(self.unexpected("',' or ')'",found))
+            }
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<Program> {
+    Parser::new(input).program()
+}
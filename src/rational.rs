@@ -0,0 +1,207 @@
+use std::ops::{Add,Sub,Mul,Div};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Zero,Signed,ToPrimitive};
+use crate::error::*;
+use crate::number::Number;
+
+/// Largest exponent an exact number is raised to by `pow`, or a decimal is written with, before
+/// falling back to floating point, as the digits of the result grow with it
+const MAX_EXACT_EXPONENT: u32 = 10_000;
+
+/// Number of the exact mode. Integers, decimals and fractions stay exact through arithmetic and
+/// comparisons, while operations with irrational results fall back to floating point.
+#[derive(Clone,Debug)]
+pub enum Rational {
+    Exact(BigRational),
+    /// Result of an operation without an exact result, like `sin`, or of anything computed from one
+    Float(f64)
+}
+
+impl Rational {
+    /// Reads a decimal like `-12.5e-3` exactly.
+    pub fn decimal(s: &str) -> Option<BigRational> {
+        let (mantissa,exp) = match s.split_once(['e','E']) {
+            Some((mantissa,exp)) => (mantissa,exp.parse::<i32>().ok()?),
+            None => (s,0)
+        };
+        let (negative,mantissa) = match mantissa.strip_prefix('-') {
+            Some(mantissa) => (true,mantissa),
+            None => (false,mantissa.strip_prefix('+').unwrap_or(mantissa))
+        };
+        let (int,frac) = mantissa.split_once('.').unwrap_or((mantissa,""));
+        if int.is_empty() && frac.is_empty() || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let exp = exp.checked_sub(i32::try_from(frac.len()).ok()?).filter(|exp| exp.unsigned_abs() <= MAX_EXACT_EXPONENT)?;
+        let n = BigRational::from_integer(format!("{int}{frac}").parse::<BigInt>().ok()?);
+        let scale = BigRational::from_integer(num_traits::pow(BigInt::from(10),exp.unsigned_abs() as usize));
+        let n = if exp < 0 { n / scale } else { n * scale };
+        Some(if negative { -n } else { n })
+    }
+
+    /// Reads a fraction like `-1/3`.
+    fn fraction(s: &str) -> Option<BigRational> {
+        let (num,den) = s.split_once('/')?;
+        let den = den.trim().parse::<BigInt>().ok().filter(|d| !d.is_zero())?;
+        Some(BigRational::new(num.trim().parse().ok()?,den))
+    }
+}
+
+impl Number for Rational {
+    /// Integers stay exact, anything else is taken as already rounded.
+    fn from_f64(n: f64) -> Self {
+        match BigRational::from_float(n) {
+            Some(r) if n.fract() == 0.0 => Rational::Exact(r),
+            _ => Rational::Float(n)
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        match self {
+            Rational::Exact(r) => r.to_f64().unwrap_or(f64::NAN),
+            Rational::Float(n) => *n
+        }
+    }
+
+    fn from_literal(n: f64) -> Self {
+        match Rational::decimal(&n.to_string()) {
+            Some(r) if n.is_finite() => Rational::Exact(r),
+            _ => Rational::Float(n)
+        }
+    }
+
+    fn from_ratio(num: f64, den: f64) -> Self {
+        match (BigRational::from_float(num),BigRational::from_float(den)) {
+            (Some(num),Some(den)) if !den.is_zero() => Rational::Exact(num / den),
+            _ => Rational::Float(num / den)
+        }
+    }
+
+    fn from_source(text: &str) -> Self {
+        match Rational::decimal(text) {
+            Some(r) => Rational::Exact(r),
+            None => Rational::Float(text.parse().unwrap_or(f64::NAN))
+        }
+    }
+
+    fn from_ratio_source(num: &str, den: &str) -> Self {
+        match Rational::fraction(&format!("{num}/{den}")) {
+            Some(r) => Rational::Exact(r),
+            None => Rational::from_ratio(num.parse().unwrap_or(f64::NAN),den.parse().unwrap_or(f64::NAN))
+        }
+    }
+
+    fn to_literal(&self) -> Option<f64> {
+        match self {
+            Rational::Exact(r) => {
+                let n = self.to_f64();
+                matches!(Rational::from_literal(n), Rational::Exact(back) if back == *r).then_some(n)
+            },
+            Rational::Float(_) => None
+        }
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        if let Some(r) = Rational::fraction(s) {
+            return Ok(Rational::Exact(r));
+        }
+        let n = s.parse::<f64>()?;
+        Ok(match Rational::decimal(s) {
+            Some(r) if n.is_finite() => Rational::Exact(r),
+            _ => Rational::Float(n)
+        })
+    }
+
+    fn is_zero(&self) -> bool {
+        match self {
+            Rational::Exact(r) => r.is_zero(),
+            Rational::Float(n) => *n == 0.0
+        }
+    }
+
+    /// Shows integers without decimals, and other exact numbers as a fraction or a mixed number
+    /// like `1 1/3`.
+    fn format(&self) -> String {
+        let r = match self {
+            Rational::Exact(r) => r,
+            Rational::Float(n) => return crate::format_num(n)
+        };
+        let sign = if r.is_negative() { "-" } else { " " };
+        let whole = r.abs().trunc();
+        let frac = r.abs() - &whole;
+        match (whole.is_zero(),frac.is_zero()) {
+            (_,true) => format!("{sign}{}",crate::group_digits(&whole.to_string())),
+            (true,false) => format!("{sign}{frac}"),
+            (false,false) => format!("{sign}{} {frac}",crate::group_digits(&whole.to_string()))
+        }
+    }
+
+    fn sqrt(self) -> Self {
+        if let Rational::Exact(r) = &self {
+            if !r.is_negative() {
+                let (num,den) = (r.numer().sqrt(),r.denom().sqrt());
+                if &(&num * &num) == r.numer() && &(&den * &den) == r.denom() {
+                    return Rational::Exact(BigRational::new(num,den));
+                }
+            }
+        }
+        Rational::from_f64(self.to_f64().sqrt())
+    }
+
+    fn powf(self, exp: Self) -> Self {
+        if let (Rational::Exact(base),Rational::Exact(e)) = (&self,&exp) {
+            if let Some(e) = e.is_integer().then(|| e.to_integer().to_i32()).flatten().filter(|e| e.unsigned_abs() <= MAX_EXACT_EXPONENT) {
+                if e >= 0 || !base.is_zero() {
+                    return Rational::Exact(num_traits::Pow::pow(base,e));
+                }
+            }
+        }
+        Rational::from_f64(self.to_f64().powf(exp.to_f64()))
+    }
+}
+
+macro_rules! rational_ops {
+    ($($op:ident $method:ident),*) => {$(
+        impl $op for Rational {
+            type Output = Rational;
+            fn $method(self, rhs: Rational) -> Rational {
+                match (self,rhs) {
+                    (Rational::Exact(lhs),Rational::Exact(rhs)) => Rational::Exact(lhs.$method(rhs)),
+                    (lhs,rhs) => Rational::Float(lhs.to_f64().$method(rhs.to_f64()))
+                }
+            }
+        }
+    )*}
+}
+
+rational_ops!(Add add, Sub sub, Mul mul);
+
+impl Div for Rational {
+    type Output = Rational;
+    /// Dividing by an exact zero gives an infinity or NaN, as it does with floats.
+    fn div(self, rhs: Rational) -> Rational {
+        match (self,rhs) {
+            (Rational::Exact(lhs),Rational::Exact(rhs)) if !rhs.is_zero() => Rational::Exact(lhs / rhs),
+            (lhs,rhs) => Rational::Float(lhs.to_f64() / rhs.to_f64())
+        }
+    }
+}
+
+impl PartialEq for Rational {
+    fn eq(&self, other: &Rational) -> bool {
+        match (self,other) {
+            (Rational::Exact(lhs),Rational::Exact(rhs)) => lhs == rhs,
+            (lhs,rhs) => lhs.to_f64() == rhs.to_f64()
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<std::cmp::Ordering> {
+        match (self,other) {
+            (Rational::Exact(lhs),Rational::Exact(rhs)) => lhs.partial_cmp(rhs),
+            (lhs,rhs) => lhs.to_f64().partial_cmp(&rhs.to_f64())
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::error::*;
+
+/// Byte range of some piece of source text.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start),self.end.max(other.end))
+    }
+
+    /// One-based line and column of the start of the span.
+    pub fn line_col(&self, source: &str) -> (usize,usize) {
+        let before = &source[..self.start.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let col = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line,col)
+    }
+}
+
+/// An error located in a named source, rendered like rustc does:
+///
+/// ```text
+/// Unknown command or operation: frob
+///  --> script.rpncalc:2:4
+///   |
+/// 2 | 1; frob;
+///   |    ^^^^
+/// ```
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub col: usize,
+    pub source_line: String,
+    pub width: usize,
+    pub error: Error
+}
+
+impl Diagnostic {
+    pub fn new(file: &str, source: &str, span: Span, error: Error) -> Self {
+        let (line,col) = span.line_col(source);
+        let source_line = source.lines().nth(line - 1).unwrap_or("").to_string();
+        let rest_of_line = source_line.chars().count() + 1 - col;
+        let span_len = source.get(span.start..span.end).map(|s| s.chars().count()).unwrap_or(0);
+        Diagnostic {
+            file: file.to_string(),
+            line,
+            col,
+            source_line,
+            width: span_len.clamp(1,rest_of_line.max(1)),
+            error
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f,"{}",self.error)?;
+        writeln!(f,"{gutter}--> {}:{}:{}",self.file,self.line,self.col)?;
+        writeln!(f,"{gutter} |")?;
+        writeln!(f,"{} | {}",self.line,self.source_line)?;
+        write!(f,"{gutter} | {}{}"," ".repeat(self.col - 1),"^".repeat(self.width))
+    }
+}
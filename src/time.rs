@@ -0,0 +1,273 @@
+use std::borrow::Cow;
+use crate::error::*;
+use crate::number::Number;
+use crate::units::Unit;
+use crate::value::Value;
+
+const MINUTE: f64 = 60.0;
+const HOUR: f64 = 3600.0;
+const DAY: f64 = 86400.0;
+
+/// Date, time of day or duration as written in a script, in seconds.
+#[derive(Clone,Copy,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum Literal {
+    /// Seconds since the Unix epoch, written as `2026-10-17` or `2026-10-17T14:30`
+    Date(f64),
+    /// Seconds since midnight, written as `14:30` or `14:30:15`
+    Clock(f64),
+    /// Seconds, written as `1h30m` with any of `w`, `d`, `h`, `m` and `s`
+    Duration(f64)
+}
+
+impl Literal {
+    /// Reads a date, a time of day or a duration, or `None` if the text is none of them. A date
+    /// whose month or day is not on the calendar is an error.
+    pub fn parse(s: &str) -> Option<Result<Literal>> {
+        if let Some((date,clock)) = s.split_once('T') {
+            let clock = clock_literal(clock)?;
+            return Some(date_literal(date)?.map(|date| Literal::Date(date + clock)));
+        }
+        date_literal(s).map(|date| date.map(Literal::Date))
+            .or_else(|| clock_literal(s).map(|clock| Ok(Literal::Clock(clock))))
+            .or_else(|| duration_literal(s).map(|duration| Ok(Literal::Duration(duration))))
+    }
+
+    /// The value the literal stands for. Times of day and durations are quantities of time, in
+    /// the largest unit they have a whole one of.
+    pub fn value<N: Number>(&self) -> Value<N> {
+        match *self {
+            Literal::Date(secs) => Value::Date(N::from_literal(secs)),
+            Literal::Clock(secs) | Literal::Duration(secs) => {
+                let (symbol,factor) = [("d",DAY),("h",HOUR),("min",MINUTE)].into_iter()
+                    .find(|(_,factor)| secs.abs() >= *factor)
+                    .unwrap_or(("s",1.0));
+                Value::Quantity(N::from_ratio(secs,factor),Unit(vec![(symbol.to_string(),1)]))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Literal::Date(secs) => {
+                let (date,clock) = split(secs);
+                if clock == 0.0 { write!(f,"{date}") } else { write!(f,"{date}T{}",format_clock(clock)) }
+            },
+            Literal::Clock(secs) => write!(f,"{}",format_clock(secs)),
+            Literal::Duration(secs) => {
+                let sign = if secs < 0.0 { "-" } else { "" };
+                let mut rest = secs.abs();
+                let mut out = String::new();
+                for (suffix,factor) in [("d",DAY),("h",HOUR),("m",MINUTE)] {
+                    let whole = (rest / factor).floor();
+                    if whole > 0.0 {
+                        out.push_str(&format!("{whole}{suffix}"));
+                        rest -= whole * factor;
+                    }
+                }
+                if rest > 0.0 || out.is_empty() {
+                    out.push_str(&format!("{}s",crate::lexer::num_source(rest)));
+                }
+                write!(f,"{sign}{out}")
+            }
+        }
+    }
+}
+
+/// Reads `YYYY-MM-DD` into seconds since the epoch at midnight, or `None` if the text does not
+/// have that shape.
+fn date_literal(s: &str) -> Option<Result<f64>> {
+    let mut parts = s.splitn(3,'-');
+    let (year,month,day) = (parts.next()?,parts.next()?,parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 || !s.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return None;
+    }
+    let (year,month,day) = (year.parse().ok()?,month.parse().ok()?,day.parse().ok()?);
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year,month)).contains(&day) {
+        return Some(Err(Error::DateRange(s.to_string())));
+    }
+    Some(Ok(days_from_civil(year,month,day) as f64 * DAY))
+}
+
+/// Reads `HH:MM` or `HH:MM:SS`, with a fraction of a second allowed, into seconds since midnight.
+fn clock_literal(s: &str) -> Option<f64> {
+    let mut parts = s.splitn(3,':');
+    let (hours,minutes,seconds) = (parts.next()?,parts.next()?,parts.next().unwrap_or("00"));
+    if !(1..=2).contains(&hours.len()) || minutes.len() != 2 || seconds.len() < 2 || !s.chars().all(|c| c.is_ascii_digit() || c == ':' || c == '.') {
+        return None;
+    }
+    let (hours,minutes,seconds) = (hours.parse::<f64>().ok()?,minutes.parse::<f64>().ok()?,seconds.parse::<f64>().ok()?);
+    (hours < 24.0 && minutes < 60.0 && seconds < 60.0).then_some(hours * HOUR + minutes * MINUTE + seconds)
+}
+
+/// Reads numbers each followed by one of `w`, `d`, `h`, `m` or `s`, like `1h30m`, into seconds.
+fn duration_literal(s: &str) -> Option<f64> {
+    let mut total = 0.0;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+        let factor = match rest[end..].chars().next()? {
+            'w' => 7.0 * DAY,
+            'd' => DAY,
+            'h' => HOUR,
+            'm' => MINUTE,
+            's' => 1.0,
+            _ => return None
+        };
+        total += rest[..end].parse::<f64>().ok()? * factor;
+        rest = &rest[end + 1..];
+    }
+    (!s.is_empty()).then_some(total)
+}
+
+fn is_leap(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 => if is_leap(year) { 29 } else { 28 },
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+/// Days since the epoch of a date of the proleptic Gregorian calendar. The year is counted from
+/// March, so that the leap day comes last.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Date of a number of days since the epoch, the reverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64,u32,u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 },month,day)
+}
+
+/// Largest number of seconds since the epoch that dates are shown for, about a million years.
+const MAX_SECS: f64 = 3e13;
+
+/// Splits seconds since the epoch into the date, as `2026-10-17`, and seconds since midnight.
+fn split(secs: f64) -> (String,f64) {
+    let days = (secs / DAY).floor();
+    let (year,month,day) = civil_from_days(days as i64);
+    (format!("{year:04}-{month:02}-{day:02}"),secs - days * DAY)
+}
+
+/// Writes seconds since midnight as `14:30`, with the seconds only when there are some.
+fn format_clock(secs: f64) -> String {
+    let (hours,minutes) = ((secs / HOUR).floor(),(secs % HOUR / MINUTE).floor());
+    let seconds = secs % MINUTE;
+    if seconds == 0.0 {
+        format!("{hours:02}:{minutes:02}")
+    } else if seconds.fract() == 0.0 {
+        format!("{hours:02}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{hours:02}:{minutes:02}:{}",format!("{seconds:06.3}").trim_end_matches('0'))
+    }
+}
+
+/// Shows a date as `2026-10-17`, with the time of day after it when it is not midnight.
+pub fn format_date<N: Number>(secs: &N) -> String {
+    let secs = secs.to_f64();
+    if secs.is_nan() || secs.abs() >= MAX_SECS {
+        return crate::format_num(&secs);
+    }
+    let (date,clock) = split(secs);
+    if clock == 0.0 { format!(" {date}") } else { format!(" {date} {}",format_clock(clock)) }
+}
+
+/// The date, as seconds since the epoch, if it is within the range that calendar operations
+/// take, or an error.
+fn in_range(secs: f64) -> Result<f64> {
+    match secs.abs() < MAX_SECS {
+        true => Ok(secs),
+        false => Err(Error::Expected("a date within a million years of 1970",crate::format_num(&secs).trim_start().to_string()))
+    }
+}
+
+/// Date the same time of day `months` after a date, or as late in the month as there is when it
+/// is shorter, so that a month after January 31 is the last day of February.
+pub fn add_months(secs: f64, months: f64) -> Result<f64> {
+    if months.fract() != 0.0 || months.abs() > MAX_SECS / DAY {
+        return Err(Error::Expected("a whole number of months",crate::format_num(&months).trim_start().to_string()));
+    }
+    let secs = in_range(secs)?;
+    let (date,clock) = ((secs / DAY).floor(),secs.rem_euclid(DAY));
+    let (year,month,day) = civil_from_days(date as i64);
+    let index = year * 12 + month as i64 - 1 + months as i64;
+    let (year,month) = (index.div_euclid(12),index.rem_euclid(12) as u32 + 1);
+    in_range(days_from_civil(year,month,day.min(days_in_month(year,month))) as f64 * DAY + clock)
+}
+
+/// Day of the week of a date, from 1 for Monday to 7 for Sunday as in ISO 8601.
+pub fn weekday(secs: f64) -> Result<f64> {
+    Ok(((in_range(secs)? / DAY).floor() as i64 + 3).rem_euclid(7) as f64 + 1.0)
+}
+
+/// What the result of an operation taking dates is.
+pub enum Dated {
+    /// A number or quantity, as for comparisons of dates
+    Plain,
+    /// A date, as for a date plus a duration
+    Date,
+    /// The time elapsed between dates, shown in days
+    Elapsed
+}
+
+/// Operations that may take dates, run by `SimpleOp` when `dimensional = true` is set. Dates
+/// are handed to them as quantities of seconds since the epoch.
+pub trait Temporal {
+    /// What the result is, given the kinds of the inputs, deepest first, at least one of which is
+    /// a date, or an error if the operation does not take them.
+    fn dates(&self, kinds: &[&'static str]) -> Result<Dated>;
+}
+
+/// Inputs with dates replaced by quantities of seconds, and what the result is.
+pub fn undate<'a,N: Number>(op: &impl Temporal, values: &'a [Value<N>]) -> Result<(Cow<'a,[Value<N>]>,Dated)> {
+    if !values.iter().any(|v| matches!(v, Value::Date(_))) {
+        return Ok((Cow::Borrowed(values),Dated::Plain));
+    }
+    let dated = op.dates(&values.iter().map(Value::kind).collect::<Vec<_>>())?;
+    let values = values.iter().map(|v| match v {
+        Value::Date(secs) => Value::Quantity(secs.clone(),Unit(vec![("s".to_string(),1)])),
+        v => v.clone()
+    }).collect();
+    Ok((Cow::Owned(values),dated))
+}
+
+/// Turns the result computed on seconds back into what [`undate`] said it is.
+pub fn redate<N: Number>(dated: Dated, value: Value<N>) -> Result<Value<N>> {
+    let seconds = Unit(vec![("s".to_string(),1)]);
+    match (dated,value) {
+        (Dated::Plain,value) => Ok(value),
+        (Dated::Date,Value::Quantity(secs,unit)) => Ok(Value::Date(unit.convert(secs,&seconds)?)),
+        (Dated::Elapsed,Value::Quantity(secs,unit)) => {
+            let days = Unit(vec![("d".to_string(),1)]);
+            Ok(Value::Quantity(unit.convert(secs,&days)?,days))
+        },
+        (_,value) => Err(Error::Type("date",value.kind()))
+    }
+}
+
+/// The date at `v`, as seconds since the epoch, or a type error.
+pub fn date_of<N: Number>(v: &Value<N>) -> Result<N> {
+    match v {
+        Value::Date(secs) => Ok(secs.clone()),
+        v => Err(Error::Type("date",v.kind()))
+    }
+}
@@ -0,0 +1,141 @@
+use std::ops::{Add,Sub,Mul,Div};
+use std::f64::consts::{LN_2,LN_10};
+use crate::error::*;
+use crate::number::Number;
+
+/// Number of the uncertainty mode: a measured value and the standard deviation of its error.
+/// Operations propagate the deviation to first order, through the derivative of the operation
+/// at the value, taking the errors of their inputs as independent. So `x - x` has a deviation
+/// of `sqrt(2)` times that of `x`, as it would for two separate measurements.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Uncertain {
+    pub value: f64,
+    pub sigma: f64
+}
+
+impl Uncertain {
+    /// Applies a function with the given derivative.
+    fn chain(self, f: fn(f64) -> f64, derivative: impl Fn(f64) -> f64) -> Uncertain {
+        Uncertain { value: f(self.value), sigma: self.scaled(derivative(self.value)) }
+    }
+
+    /// Deviation of the value times `factor`, which is zero for exact values even when the
+    /// factor is infinite or NaN.
+    fn scaled(&self, factor: f64) -> f64 {
+        if self.sigma == 0.0 { 0.0 } else { (factor * self.sigma).abs() }
+    }
+}
+
+/// Rounds the deviation to one significant digit, or two when the first is a 1, and the value
+/// to the same decimal place, returning both and the number of decimals.
+fn round(value: f64, sigma: f64) -> (f64,f64,usize) {
+    let digits = |sigma: f64| {
+        let exp = sigma.log10().floor() as i32;
+        let leading = sigma / 10f64.powi(exp);
+        exp - if leading < 2.0 { 1 } else { 0 }
+    };
+    let mut place = digits(sigma);
+    let mut rounded = (sigma / 10f64.powi(place)).round() * 10f64.powi(place);
+    if digits(rounded) != place {
+        place = digits(rounded);
+        rounded = (sigma / 10f64.powi(place)).round() * 10f64.powi(place);
+    }
+    ((value / 10f64.powi(place)).round() * 10f64.powi(place),rounded,(-place).max(0) as usize)
+}
+
+impl Number for Uncertain {
+    fn from_f64(n: f64) -> Self {
+        Uncertain { value: n, sigma: 0.0 }
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.value
+    }
+
+    fn from_plus_minus(mid: f64, radius: f64) -> Option<Self> {
+        Some(Uncertain { value: mid, sigma: radius.abs() })
+    }
+
+    /// Only exact values are literals.
+    fn to_literal(&self) -> Option<f64> {
+        (self.sigma == 0.0).then_some(self.value)
+    }
+
+    /// Reads numbers, and measurements written as `12.3±0.4`.
+    fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match crate::lexer::plus_minus_literal(s) {
+            Some((value,sigma)) => Uncertain::from_plus_minus(value,sigma).ok_or(Error::PlusMinus),
+            None => Ok(Uncertain::from_f64(s.parse()?))
+        }
+    }
+
+    /// Shows measurements as `12.3 ± 0.4`, with the value rounded to the last significant digit
+    /// of the deviation, and exact values as in float mode.
+    fn format(&self) -> String {
+        if self.sigma == 0.0 || !self.sigma.is_finite() || !self.value.is_finite() {
+            let n = crate::format_num(&self.value);
+            return if self.sigma == 0.0 { n } else { format!("{n} ± {}",crate::format_num(&self.sigma).trim_start()) };
+        }
+        let (value,sigma,decimals) = round(self.value,self.sigma);
+        let sign = if value.is_sign_negative() && value != 0.0 { "-" } else { " " };
+        format!("{sign}{} ± {}",crate::group_digits(&format!("{:.decimals$}",value.abs())),crate::group_digits(&format!("{sigma:.decimals$}")).trim_start())
+    }
+
+    fn log10(self) -> Self { self.chain(f64::log10,|x| 1.0 / (x * LN_10)) }
+    fn log2(self) -> Self { self.chain(f64::log2,|x| 1.0 / (x * LN_2)) }
+    fn ln(self) -> Self { self.chain(f64::ln,|x| 1.0 / x) }
+    fn sqrt(self) -> Self { self.chain(f64::sqrt,|x| 0.5 / x.sqrt()) }
+
+    fn powf(self, exp: Self) -> Self {
+        let value = self.value.powf(exp.value);
+        let sigma = self.scaled(exp.value * self.value.powf(exp.value - 1.0)).hypot(exp.scaled(value * self.value.ln()));
+        Uncertain { value, sigma }
+    }
+
+    fn log(self, base: Self) -> Self { self.ln() / base.ln() }
+    fn recip(self) -> Self { self.chain(f64::recip,|x| 1.0 / (x * x)) }
+    fn sin(self) -> Self { self.chain(f64::sin,f64::cos) }
+    fn cos(self) -> Self { self.chain(f64::cos,f64::sin) }
+    fn tan(self) -> Self { self.chain(f64::tan,|x| 1.0 / (x.cos() * x.cos())) }
+    fn asin(self) -> Self { self.chain(f64::asin,|x| 1.0 / (1.0 - x * x).sqrt()) }
+    fn acos(self) -> Self { self.chain(f64::acos,|x| 1.0 / (1.0 - x * x).sqrt()) }
+    fn atan(self) -> Self { self.chain(f64::atan,|x| 1.0 / (1.0 + x * x)) }
+    fn abs(self) -> Self { Uncertain { value: self.value.abs(), sigma: self.sigma } }
+}
+
+impl Add for Uncertain {
+    type Output = Uncertain;
+    fn add(self, rhs: Uncertain) -> Uncertain {
+        Uncertain { value: self.value + rhs.value, sigma: self.sigma.hypot(rhs.sigma) }
+    }
+}
+
+impl Sub for Uncertain {
+    type Output = Uncertain;
+    fn sub(self, rhs: Uncertain) -> Uncertain {
+        Uncertain { value: self.value - rhs.value, sigma: self.sigma.hypot(rhs.sigma) }
+    }
+}
+
+impl Mul for Uncertain {
+    type Output = Uncertain;
+    fn mul(self, rhs: Uncertain) -> Uncertain {
+        Uncertain { value: self.value * rhs.value, sigma: self.scaled(rhs.value).hypot(rhs.scaled(self.value)) }
+    }
+}
+
+impl Div for Uncertain {
+    type Output = Uncertain;
+    fn div(self, rhs: Uncertain) -> Uncertain {
+        let value = self.value / rhs.value;
+        Uncertain { value, sigma: self.scaled(1.0 / rhs.value).hypot(rhs.scaled(value / rhs.value)) }
+    }
+}
+
+/// Measurements are ordered by their values, then by their deviations.
+impl PartialOrd for Uncertain {
+    fn partial_cmp(&self, other: &Uncertain) -> Option<std::cmp::Ordering> {
+        (self.value,self.sigma).partial_cmp(&(other.value,other.sigma))
+    }
+}
@@ -0,0 +1,222 @@
+use crate::error::*;
+use crate::number::Number;
+use crate::value::Value;
+
+/// Exponents of the SI base units metre, kilogram, second, ampere, kelvin, mole and candela.
+type Dims = [i32;7];
+
+/// Largest power a unit is raised to, in either direction, within a unit.
+const MAX_POWER: i32 = 100;
+
+/// Symbols of the known units, how many of their SI unit one of them is, and their dimension.
+/// Prefixed units are listed on their own. Units whose zero is offset from that of their SI
+/// unit, like degrees Celsius, cannot be expressed as a factor and are left out.
+const UNITS: &[(&str,f64,Dims)] = &[
+    ("m",1.0,[1,0,0,0,0,0,0]),
+    ("km",1000.0,[1,0,0,0,0,0,0]),
+    ("cm",0.01,[1,0,0,0,0,0,0]),
+    ("mm",0.001,[1,0,0,0,0,0,0]),
+    ("um",1e-6,[1,0,0,0,0,0,0]),
+    ("nm",1e-9,[1,0,0,0,0,0,0]),
+    ("in",0.0254,[1,0,0,0,0,0,0]),
+    ("ft",0.3048,[1,0,0,0,0,0,0]),
+    ("yd",0.9144,[1,0,0,0,0,0,0]),
+    ("mi",1609.344,[1,0,0,0,0,0,0]),
+    ("nmi",1852.0,[1,0,0,0,0,0,0]),
+    ("kg",1.0,[0,1,0,0,0,0,0]),
+    ("g",0.001,[0,1,0,0,0,0,0]),
+    ("mg",1e-6,[0,1,0,0,0,0,0]),
+    ("t",1000.0,[0,1,0,0,0,0,0]),
+    ("lb",0.45359237,[0,1,0,0,0,0,0]),
+    ("oz",0.028349523125,[0,1,0,0,0,0,0]),
+    ("s",1.0,[0,0,1,0,0,0,0]),
+    ("ms",0.001,[0,0,1,0,0,0,0]),
+    ("us",1e-6,[0,0,1,0,0,0,0]),
+    ("min",60.0,[0,0,1,0,0,0,0]),
+    ("h",3600.0,[0,0,1,0,0,0,0]),
+    ("d",86400.0,[0,0,1,0,0,0,0]),
+    ("A",1.0,[0,0,0,1,0,0,0]),
+    ("mA",0.001,[0,0,0,1,0,0,0]),
+    ("K",1.0,[0,0,0,0,1,0,0]),
+    ("mol",1.0,[0,0,0,0,0,1,0]),
+    ("cd",1.0,[0,0,0,0,0,0,1]),
+    ("Hz",1.0,[0,0,-1,0,0,0,0]),
+    ("rpm",1.0 / 60.0,[0,0,-1,0,0,0,0]),
+    ("L",0.001,[3,0,0,0,0,0,0]),
+    ("mL",1e-6,[3,0,0,0,0,0,0]),
+    ("gal",0.003785411784,[3,0,0,0,0,0,0]),
+    ("N",1.0,[1,1,-2,0,0,0,0]),
+    ("kN",1000.0,[1,1,-2,0,0,0,0]),
+    ("lbf",4.4482216152605,[1,1,-2,0,0,0,0]),
+    ("J",1.0,[2,1,-2,0,0,0,0]),
+    ("kJ",1000.0,[2,1,-2,0,0,0,0]),
+    ("cal",4.184,[2,1,-2,0,0,0,0]),
+    ("kcal",4184.0,[2,1,-2,0,0,0,0]),
+    ("Wh",3600.0,[2,1,-2,0,0,0,0]),
+    ("kWh",3.6e6,[2,1,-2,0,0,0,0]),
+    ("W",1.0,[2,1,-3,0,0,0,0]),
+    ("kW",1000.0,[2,1,-3,0,0,0,0]),
+    ("hp",745.6998715822702,[2,1,-3,0,0,0,0]),
+    ("Pa",1.0,[-1,1,-2,0,0,0,0]),
+    ("kPa",1000.0,[-1,1,-2,0,0,0,0]),
+    ("MPa",1e6,[-1,1,-2,0,0,0,0]),
+    ("bar",1e5,[-1,1,-2,0,0,0,0]),
+    ("atm",101325.0,[-1,1,-2,0,0,0,0]),
+    ("psi",6894.757293168361,[-1,1,-2,0,0,0,0]),
+    ("C",1.0,[0,0,1,1,0,0,0]),
+    ("V",1.0,[2,1,-3,-1,0,0,0]),
+    ("ohm",1.0,[2,1,-3,-2,0,0,0])
+];
+
+fn lookup(symbol: &str) -> Option<&'static (&'static str,f64,Dims)> {
+    UNITS.iter().find(|(s,_,_)| *s == symbol)
+}
+
+/// Unit of a quantity, as units of the table raised to integer powers, like `kg*m/s^2`.
+#[derive(Clone,Debug,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Unit(pub Vec<(String,i32)>);
+
+impl Unit {
+    /// Reads units of the table joined by `*` and `/`, each optionally raised to an integer
+    /// with `^` of at most [`MAX_POWER`]. A leading `1` stands for no unit, as in `1/s`.
+    pub fn parse(s: &str) -> Result<Unit> {
+        let mut unit = Unit(vec![]);
+        let mut sign = 1;
+        let mut rest = s;
+        loop {
+            let end = rest.find(['*','/']).unwrap_or(rest.len());
+            let (symbol,power) = match rest[..end].split_once('^') {
+                Some((symbol,power)) => match power.parse::<i32>() {
+                    Ok(power) if power.abs() <= MAX_POWER => (symbol,power),
+                    _ => return Err(Error::UnknownUnit(s.to_string()))
+                },
+                None => (&rest[..end],1)
+            };
+            if !(symbol == "1" && unit.0.is_empty() && power == 1) {
+                let (symbol,_,_) = lookup(symbol).ok_or_else(|| Error::UnknownUnit(symbol.to_string()))?;
+                unit = unit.mul(&Unit(vec![(symbol.to_string(),sign * power)]))?;
+            }
+            match rest[end..].chars().next() {
+                Some(op) => {
+                    sign = if op == '*' { 1 } else { -1 };
+                    rest = &rest[end + 1..];
+                },
+                None => break Ok(unit)
+            }
+        }
+    }
+
+    fn dims(&self) -> Dims {
+        let mut dims: Dims = [0;7];
+        for (symbol,power) in &self.0 {
+            if let Some((_,_,base)) = lookup(symbol) {
+                for (d,b) in dims.iter_mut().zip(base) {
+                    *d = d.saturating_add(b.saturating_mul(*power));
+                }
+            }
+        }
+        dims
+    }
+
+    /// How many of its SI unit one of this unit is.
+    fn factor<N: Number>(&self) -> N {
+        let mut factor = N::from_f64(1.0);
+        for (symbol,power) in &self.0 {
+            let unit = lookup(symbol).map_or(N::from_f64(f64::NAN),|(_,f,_)| N::from_literal(*f));
+            for _ in 0..power.unsigned_abs() {
+                factor = if *power > 0 { factor * unit.clone() } else { factor / unit.clone() };
+            }
+        }
+        factor
+    }
+
+    /// Product of the units, where powers of the same unit add up, failing if one of them gets
+    /// past [`MAX_POWER`].
+    pub fn mul(&self, other: &Unit) -> Result<Unit> {
+        let mut out = self.0.clone();
+        for (symbol,power) in &other.0 {
+            match out.iter_mut().find(|(s,_)| s == symbol) {
+                Some((_,p)) => *p = p.saturating_add(*power),
+                None => out.push((symbol.clone(),*power))
+            }
+        }
+        let unit = Unit(out.into_iter().filter(|(_,p)| *p != 0).collect());
+        match unit.0.iter().all(|(_,p)| p.abs() <= MAX_POWER) {
+            true => Ok(unit),
+            false => Err(Error::UnknownUnit(unit.to_string()))
+        }
+    }
+
+    pub fn recip(&self) -> Unit {
+        Unit(self.0.iter().map(|(s,p)| (s.clone(),-p)).collect())
+    }
+
+    /// Converts a number in this unit to `into`, failing if they measure different things.
+    pub fn convert<N: Number>(&self, n: N, into: &Unit) -> Result<N> {
+        if self.dims() != into.dims() {
+            return Err(Error::Units(self.to_string(),into.to_string()));
+        }
+        Ok(n * self.factor::<N>() / into.factor::<N>())
+    }
+
+    /// Describes the unit of a value for error messages.
+    fn describe(unit: Option<&Unit>) -> String {
+        unit.map_or("no unit".to_string(),Unit::to_string)
+    }
+}
+
+impl std::fmt::Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let term = |s: &str,p: i32| if p == 1 { s.to_string() } else { format!("{s}^{p}") };
+        let num = self.0.iter().filter(|(_,p)| *p > 0).map(|(s,p)| term(s,*p)).intersperse("*".to_string()).collect::<String>();
+        write!(f,"{}",if num.is_empty() { "1" } else { &num })?;
+        for (s,p) in self.0.iter().filter(|(_,p)| *p < 0) {
+            write!(f,"/{}",term(s,-p))?;
+        }
+        Ok(())
+    }
+}
+
+/// A number with a unit, or a plain number when the unit measures nothing, as the unit of
+/// `m/km` does.
+pub fn quantity<N: Number>(n: N, unit: Option<Unit>) -> Value<N> {
+    match unit {
+        Some(unit) if unit.dims() == [0;7] => Value::Num(n * unit.factor::<N>()),
+        Some(unit) => Value::Quantity(n,unit),
+        None => Value::Num(n)
+    }
+}
+
+/// Operations whose inputs may have units, run by `SimpleOp` when `dimensional = true` is set.
+pub trait Dimensional {
+    /// Units to convert each input to before computing, and the unit of the result, given the
+    /// units of the inputs, deepest first.
+    fn units(&self, units: &[Option<&Unit>]) -> Result<(Vec<Option<Unit>>,Option<Unit>)>;
+}
+
+/// Numbers of the inputs, deepest first, converted to the units the operation asks for, and the
+/// unit of its result.
+pub fn convert<N: Number>(op: &impl Dimensional, values: &[Value<N>]) -> Result<(Vec<N>,Option<Unit>)> {
+    let units = values.iter().map(Value::unit).collect::<Vec<_>>();
+    let (into,out) = op.units(&units)?;
+    let nums = values.iter().zip(into).map(|(v,into)| {
+        let n = v.as_quantity()?;
+        match (v.unit(),into) {
+            (Some(unit),Some(into)) => unit.convert(n,&into),
+            _ => Ok(n)
+        }
+    }).collect::<Result<Vec<_>>>()?;
+    Ok((nums,out))
+}
+
+/// For operations like `+` and `<`, whose inputs must measure the same thing. The second input
+/// is converted to the unit of the first.
+pub fn same_units(units: &[Option<&Unit>]) -> Result<(Vec<Option<Unit>>,Option<Unit>)> {
+    match units {
+        [None,None] => Ok((vec![None,None],None)),
+        [Some(lhs),Some(rhs)] if lhs.dims() == rhs.dims() => Ok((vec![None,Some((*lhs).clone())],Some((*lhs).clone()))),
+        [lhs,rhs] => Err(Error::Units(Unit::describe(*lhs),Unit::describe(*rhs))),
+        _ => unreachable!("operations with same units take two inputs")
+    }
+}
@@ -0,0 +1,94 @@
+use crate::error::*;
+use crate::number::Number;
+use crate::Chain;
+use crate::units::Unit;
+use crate::array::Array;
+
+/// An element of the stack, holding numbers of the backend `N`.
+#[derive(Clone)]
+pub enum Value<N = f64> {
+    Num(N),
+    /// A number with a unit, like `9.81 m/s^2`
+    Quantity(N,Unit),
+    /// A vector or matrix, like `[[1 2] [3 4]]`
+    Array(Array<N>),
+    /// A date and time of day, as seconds since the Unix epoch in UTC
+    Date(N),
+    /// A chain pushed without running it, to be run later by a combinator
+    Quote(Chain)
+}
+
+impl<N: Number> Value<N> {
+    /// Name of the kind of value, for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Num(_) => "number",
+            Value::Quantity(_,_) => "quantity",
+            Value::Array(_) => "array",
+            Value::Date(_) => "date",
+            Value::Quote(_) => "quotation"
+        }
+    }
+
+    pub fn as_num(&self) -> Result<N> {
+        match self {
+            Value::Num(n) => Ok(n.clone()),
+            v => Err(Error::Type("number",v.kind()))
+        }
+    }
+
+    /// The number of a number or of a quantity, without its unit.
+    pub fn as_quantity(&self) -> Result<N> {
+        match self {
+            Value::Num(n) | Value::Quantity(n,_) => Ok(n.clone()),
+            v => Err(Error::Type("number",v.kind()))
+        }
+    }
+
+    pub fn unit(&self) -> Option<&Unit> {
+        match self {
+            Value::Quantity(_,unit) => Some(unit),
+            _ => None
+        }
+    }
+
+    pub fn into_quote(self) -> Result<Chain> {
+        match self {
+            Value::Quote(c) => Ok(c),
+            v => Err(Error::Type("quotation",v.kind()))
+        }
+    }
+}
+
+impl<N: Number> From<N> for Value<N> {
+    fn from(n: N) -> Self {
+        Value::Num(n)
+    }
+}
+
+impl<N: Number> std::fmt::Display for Value<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Num(n) => write!(f,"{}",n.format()),
+            Value::Quantity(n,unit) => write!(f,"{} {unit}",n.format()),
+            Value::Array(a) => write!(f,"{a}"),
+            Value::Date(secs) => write!(f,"{}",crate::time::format_date(secs)),
+            Value::Quote(c) => write!(f,"{}",c.block())
+        }
+    }
+}
+
+/// Pops the top of the stack if it is a number, leaving the stack untouched otherwise.
+pub fn pop_num<N: Number>(stack: &mut Vec<Value<N>>) -> Result<N> {
+    let n = stack.last().ok_or(Error::StackEmpty(0, 1))?.as_num()?;
+    stack.pop();
+    Ok(n)
+}
+
+/// Pops the top of the stack if it is a quotation, leaving the stack untouched otherwise.
+pub fn pop_quote<N: Number>(stack: &mut Vec<Value<N>>) -> Result<Chain> {
+    if let Some(v) = stack.last().filter(|v| !matches!(v, Value::Quote(_))) {
+        return Err(Error::Type("quotation",v.kind()));
+    }
+    stack.pop().ok_or(Error::StackEmpty(0, 1))?.into_quote()
+}
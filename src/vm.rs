@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::error::*;
+use crate::span::Span;
+use crate::context::Context;
+use crate::value::*;
+use crate::number::Number;
+use crate::{Chain,CommandOrOp,CommandEnum,Command,Conditional,ConditionalKind,Repeat,MAX_REPETITIONS};
+
+/// Flat form of a chain, where control flow is done with jumps to instruction indices instead
+/// of nested commands, so that loops neither clone their body nor recurse on each iteration.
+///
+/// Commands that run chains taken from the stack or from other files, like `exec` and
+/// `include`, still run through the tree interpreter.
+#[derive(Clone)]
+pub struct Code(Vec<Instr>);
+
+#[derive(Clone)]
+enum Instr {
+    /// A command or operation that does not affect control flow, run on its own
+    Run(CommandOrOp),
+    /// Sets the output to nothing, as an empty chain does
+    ClearOut,
+    Jump(usize),
+    /// Pops a number and jumps if it is nonzero
+    IfNonZero(usize,Span),
+    /// Runs whatever follows until `TryEnd`, jumping to the handler if it fails
+    TryBegin(usize),
+    TryEnd,
+    /// Runs a word, by its upper-cased name and the name as written
+    Call(String,String,Span),
+    LoopBounded(usize),
+    LoopUnbounded,
+    LoopWhile,
+    LoopFor([f64;3],Option<String>,Span),
+    /// Starts the next iteration of the innermost loop, or jumps out of it when it is done
+    Next(usize,Span),
+    /// Pops the result of a while condition and jumps out of the loop if it is zero
+    WhileCond(usize,Span),
+    /// Ends an iteration, keeping its output, and jumps back to the loop's `Next`
+    Continue(usize),
+    /// Leaves the innermost loop with the output of its last iteration
+    EndLoop
+}
+
+impl Instr {
+    fn target_mut(&mut self) -> Option<&mut usize> {
+        match self {
+            Instr::Jump(t) | Instr::IfNonZero(t,_) | Instr::TryBegin(t) | Instr::Next(t,_) | Instr::WhileCond(t,_) | Instr::Continue(t) => Some(t),
+            _ => None
+        }
+    }
+}
+
+impl Code {
+    pub fn compile(chain: &Chain) -> Self {
+        let mut code = Code(vec![]);
+        code.chain(chain);
+        code
+    }
+
+    fn emit(&mut self, instr: Instr) -> usize {
+        self.0.push(instr);
+        self.0.len() - 1
+    }
+
+    /// Points the jump at `at` to the next instruction to be emitted.
+    fn patch(&mut self, at: usize) {
+        let here = self.0.len();
+        if let Some(target) = self.0[at].target_mut() {
+            *target = here;
+        }
+    }
+
+    fn chain(&mut self, chain: &Chain) {
+        if chain.0.is_empty() {
+            self.emit(Instr::ClearOut);
+        }
+        for c in &chain.0 {
+            self.command(c);
+        }
+    }
+
+    fn command(&mut self, c: &CommandOrOp) {
+        let span = c.span();
+        match c {
+            CommandOrOp::Command(CommandEnum::Chain(chain),_,_) => self.chain(chain),
+            CommandOrOp::Command(CommandEnum::Conditional(Conditional(kind,[first,second])),_,_) => {
+                let branch = match kind {
+                    ConditionalKind::If => self.emit(Instr::IfNonZero(0,span)),
+                    ConditionalKind::Try => self.emit(Instr::TryBegin(0))
+                };
+                self.chain(first);
+                if let ConditionalKind::Try = kind {
+                    self.emit(Instr::TryEnd);
+                }
+                let skip = self.emit(Instr::Jump(0));
+                self.patch(branch);
+                self.chain(second);
+                self.patch(skip);
+            },
+            CommandOrOp::Command(CommandEnum::Repeat(repeat),_,_) => {
+                match repeat {
+                    Repeat::Bounded(amount,_) => self.emit(Instr::LoopBounded(*amount)),
+                    Repeat::Unbounded(_) => self.emit(Instr::LoopUnbounded),
+                    Repeat::While(_) => self.emit(Instr::LoopWhile),
+                    Repeat::For(bounds,register,_) => self.emit(Instr::LoopFor(*bounds,register.clone(),span))
+                };
+                let start = self.0.len();
+                let next = self.emit(Instr::Next(0,span));
+                let cond = match repeat {
+                    Repeat::Bounded(_,body) | Repeat::Unbounded(body) => { self.command(body); None },
+                    Repeat::While([cond,body]) => {
+                        self.chain(cond);
+                        let exit = self.emit(Instr::WhileCond(0,span));
+                        self.chain(body);
+                        Some(exit)
+                    },
+                    Repeat::For(_,_,body) => { self.chain(body); None }
+                };
+                self.emit(Instr::Continue(start));
+                self.patch(next);
+                if let Some(exit) = cond {
+                    self.patch(exit);
+                }
+                self.emit(Instr::EndLoop);
+            },
+            CommandOrOp::Command(CommandEnum::Call(call),_,_) => { self.emit(Instr::Call(call.0.to_uppercase(),call.0.clone(),span)); },
+            CommandOrOp::Command(c,_,_) => { self.emit(Instr::Run(CommandOrOp::Command(c.clone(),span,vec![]))); },
+            CommandOrOp::Op(o,_,_) => { self.emit(Instr::Run(CommandOrOp::Op(o.clone(),span,vec![]))); }
+        }
+    }
+}
+
+enum LoopKind {
+    Bounded(usize),
+    Unbounded(usize),
+    While(usize),
+    /// Start and step of the index, number of iterations, register the index goes in, and the
+    /// current iteration counting from 1 along with its index
+    For(f64,f64,usize,Option<String>,usize,f64)
+}
+
+struct Loop {
+    kind: LoopKind,
+    /// Output of the last finished iteration
+    out: Option<String>
+}
+
+struct Handler {
+    target: usize,
+    /// Whether this ends an unbounded repeat rather than starting a try's fallback chain
+    ends_repeat: bool,
+    frames: usize,
+    loops: usize,
+    depth: usize
+}
+
+/// What to do after an instruction, besides moving on to the next one.
+enum Flow {
+    Next,
+    Jump(usize),
+    Call(Rc<Code>)
+}
+
+impl Command for Code {
+    fn comm<N: Number>(self, stack: &mut Vec<Value<N>>, ctx: &mut Context<N>, mut stdin: impl std::io::Read, mut stdout: impl std::io::Write) -> Result<Option<String>> {
+        let initial_depth = ctx.depth;
+        let mut compiled: HashMap<String,(Rc<Chain>,Rc<Code>)> = HashMap::new();
+        let mut code = Rc::new(self);
+        let mut pc = 0;
+        // Code and return position of every word call in progress
+        let mut frames: Vec<(Rc<Code>,usize)> = vec![];
+        let mut loops: Vec<Loop> = vec![];
+        let mut handlers: Vec<Handler> = vec![];
+        let mut out = None;
+        loop {
+            let Some(instr) = code.0.get(pc) else {
+                match frames.pop() {
+                    Some((caller,ret)) => {
+                        code = caller;
+                        pc = ret;
+                        ctx.depth -= 1;
+                        continue;
+                    },
+                    None => return Ok(out)
+                }
+            };
+            pc += 1;
+            let res = (|| Ok(match instr {
+                Instr::Run(c) => {
+                    out = c.clone().comm(stack,ctx,&mut stdin,&mut stdout)?;
+                    Flow::Next
+                },
+                Instr::ClearOut => { out = None; Flow::Next },
+                Instr::Jump(target) => Flow::Jump(*target),
+                Instr::IfNonZero(target,span) => if !pop_num(stack).map_err(|e| e.at(*span))?.is_zero() { Flow::Jump(*target) } else { Flow::Next },
+                Instr::TryBegin(target) => {
+                    handlers.push(Handler { target: *target, ends_repeat: false, frames: frames.len(), loops: loops.len(), depth: ctx.depth });
+                    Flow::Next
+                },
+                Instr::TryEnd => { handlers.pop(); Flow::Next },
+                Instr::Call(name,written,span) => {
+                    let chain = ctx.words.get(name).cloned().ok_or_else(|| Error::UnknownWord(written.clone()).at(*span))?;
+                    if ctx.depth >= ctx.max_depth {
+                        return Err(Error::RecursionLimit(ctx.max_depth).at(*span));
+                    }
+                    ctx.depth += 1;
+                    match compiled.get(name) {
+                        Some((defined,code)) if Rc::ptr_eq(defined,&chain) => Flow::Call(code.clone()),
+                        _ => {
+                            let code = Rc::new(Code::compile(&chain));
+                            compiled.insert(name.clone(),(chain,code.clone()));
+                            Flow::Call(code)
+                        }
+                    }
+                },
+                Instr::LoopBounded(amount) => { loops.push(Loop { kind: LoopKind::Bounded(*amount), out: None }); Flow::Next },
+                Instr::LoopUnbounded => { loops.push(Loop { kind: LoopKind::Unbounded(0), out: None }); Flow::Next },
+                Instr::LoopWhile => { loops.push(Loop { kind: LoopKind::While(0), out: None }); Flow::Next },
+                Instr::LoopFor([start,end,step],register,span) => {
+                    let reps = ((end - start) / step).floor() + 1.0;
+                    if reps.is_nan() || reps >= MAX_REPETITIONS as f64 {
+                        return Err(Error::InfLoop.at(*span));
+                    }
+                    loops.push(Loop { kind: LoopKind::For(*start,*step,reps.max(0.0) as usize,register.clone(),0,*start), out: None });
+                    Flow::Next
+                },
+                Instr::Next(exit,span) => match &mut loops.last_mut().unwrap().kind {
+                    LoopKind::Bounded(0) => Flow::Jump(*exit),
+                    LoopKind::Bounded(remaining) => { *remaining -= 1; Flow::Next },
+                    LoopKind::Unbounded(count) | LoopKind::While(count) if *count >= MAX_REPETITIONS => return Err(Error::InfLoop.at(*span)),
+                    LoopKind::Unbounded(count) => {
+                        *count += 1;
+                        handlers.push(Handler { target: *exit, ends_repeat: true, frames: frames.len(), loops: loops.len(), depth: ctx.depth });
+                        Flow::Next
+                    },
+                    LoopKind::While(count) => { *count += 1; Flow::Next },
+                    LoopKind::For(_,_,reps,_,iteration,_) if iteration == reps => Flow::Jump(*exit),
+                    LoopKind::For(start,step,_,register,iteration,index) => {
+                        *index = *start + *iteration as f64 * *step;
+                        *iteration += 1;
+                        match register {
+                            Some(name) => { ctx.registers.insert(name.clone(),Value::Num(Repeat::index(*start,*step,*iteration - 1))); },
+                            None => stack.push(Value::Num(Repeat::index(*start,*step,*iteration - 1)))
+                        }
+                        Flow::Next
+                    }
+                },
+                Instr::WhileCond(exit,span) => if pop_num(stack).map_err(|e| e.at(*span))?.is_zero() { Flow::Jump(*exit) } else { Flow::Next },
+                Instr::Continue(start) => {
+                    let l = loops.last_mut().unwrap();
+                    l.out = out.take();
+                    if let LoopKind::Unbounded(_) = l.kind {
+                        handlers.pop();
+                    }
+                    Flow::Jump(*start)
+                },
+                Instr::EndLoop => {
+                    out = loops.pop().unwrap().out;
+                    Flow::Next
+                }
+            }))();
+            match res {
+                Ok(Flow::Next) => (),
+                Ok(Flow::Jump(target)) => pc = target,
+                Ok(Flow::Call(callee)) => {
+                    frames.push((std::mem::replace(&mut code,callee),pc));
+                    pc = 0;
+                },
+                Err(mut e) => {
+                    let handler = handlers.pop();
+                    let keep_loops = handler.as_ref().map_or(0,|h| h.loops);
+                    while loops.len() > keep_loops {
+                        if let Some(Loop { kind: LoopKind::For(_,_,_,_,iteration,index), .. }) = loops.pop() {
+                            e = e.in_iteration(iteration,index);
+                        }
+                    }
+                    let Some(handler) = handler else {
+                        ctx.depth = initial_depth;
+                        return Err(e);
+                    };
+                    if frames.len() > handler.frames {
+                        code = frames[handler.frames].0.clone();
+                        frames.truncate(handler.frames);
+                    }
+                    ctx.depth = handler.depth;
+                    if handler.ends_repeat {
+                        loops.last_mut().unwrap().out = Some(format!("Ended Repetitions with the following error: \n{e}"));
+                    }
+                    pc = handler.target;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use rpncalc::error::Error;
+use rpncalc::rational::Rational;
+
+#[test]
+fn literals() {
+    assert_eq!(common::stack::<Rational>("[1 2 3]; [[1 2] [3 4]]"),["[1, 2, 3]","[[1, 2] [3, 4]]"]);
+    assert_eq!(common::stack::<Rational>("1; 2; 3; 4; 5; 6; mat 2 3; 7; 8; vec 2"),["[[1, 2, 3] [4, 5, 6]]","[7, 8]"]);
+    assert_eq!(common::stack::<Rational>("[1]; [1; 2]").len(),2);
+    assert!(matches!(common::error::<Rational>("[[1 2] [3]]").inner(),Error::Shape(_,_)));
+}
+
+#[test]
+fn elementwise() {
+    assert_eq!(common::stack::<Rational>("[1 2 3]; [4 5 6]; +"),["[5, 7, 9]"]);
+    assert_eq!(common::stack::<Rational>("[[1 2] [3 4]]; 10; *; 1; [1 2]; /"),["[[10, 20] [30, 40]]","[1, 1/2]"]);
+    assert_eq!(common::stack::<Rational>("[1 4 9]; root2"),["[1, 2, 3]"]);
+    assert!(matches!(common::error::<Rational>("[1 2 3]; [1 2]; +").inner(),Error::Shape(_,_)));
+}
+
+#[test]
+fn products() {
+    assert_eq!(common::stack::<Rational>("[1 2 3]; [4 5 6]; dot"),["32"]);
+    assert_eq!(common::stack::<Rational>("[[1 2] [3 4]]; [[5 6] [7 8]]; dot; [[1 2] [3 4]]; [1 1]; dot"),["[[19, 22] [43, 50]]","[3, 7]"]);
+    assert_eq!(common::stack::<Rational>("[1 0 0]; [0 1 0]; cross"),["[0, 0, 1]"]);
+    assert!(matches!(common::error::<Rational>("[1 2]; [3 4]; cross").inner(),Error::Shape(_,_)));
+    assert!(matches!(common::error::<Rational>("[[1 2 3] [4 5 6]]; [1 2]; dot").inner(),Error::Shape(_,_)));
+}
+
+#[test]
+fn matrices() {
+    assert_eq!(common::stack::<Rational>("[[1 2 3] [4 5 6]]; transpose"),["[[1, 4] [2, 5] [3, 6]]"]);
+    assert_eq!(common::stack::<Rational>("[[2 0] [1 3]]; det; [[1 2] [2 4]]; det"),["6","0"]);
+    assert_eq!(common::stack::<Rational>("[[4 7] [2 6]]; inv"),["[[3/5, -7/10] [-1/5, 2/5]]"]);
+    assert_eq!(common::stack::<Rational>("[[2 1 -1] [-3 -1 2] [-2 1 2]]; [8 -11 -3]; solve"),["[2, 3, -1]"]);
+    assert_eq!(common::stack::<f64>("[[0 1] [1 0]]; [2 3]; solve"),["[3.000 000 000, 2.000 000 000]"]);
+    assert!(matches!(common::error::<Rational>("[[1 2] [2 4]]; inv").inner(),Error::Singular));
+    assert!(matches!(common::error::<Rational>("[[1 2 3] [4 5 6]]; det").inner(),Error::Shape(_,_)));
+    assert!(matches!(common::error::<Rational>("1; det").inner(),Error::Type("array","number")));
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use rpncalc::big::Big;
+
+#[test]
+fn powers_keep_every_digit() {
+    assert_eq!(common::stack::<Big>("2; 100; pow"),["1 267 650 600 228 229 401 496 703 205 376"]);
+    assert_eq!(common::stack::<Big>("-3; 41; pow"),["- 36 472 996 377 170 786 403"]);
+    assert_eq!(common::stack::<Big>("2; 10; pow; 2; 0.5; pow"),["1 024.000 000 000","1.414 213 562"]);
+    assert_eq!(common::stack::<Big>("10; 100000; pow; 100000; pow"),["Inf"]);
+}
+
+#[test]
+fn arithmetic_past_the_safe_range() {
+    assert_eq!(common::stack::<Big>("def fact [dup; 1; gt; if [d; 1] [dup; 1; -; fact; *]]; 25; fact"),["15 511 210 043 330 985 984 000 000"]);
+    assert_eq!(common::stack::<Big>("9007199254740991; 2; +; 1; -"),["9 007 199 254 740 992"]);
+    assert_eq!(common::stack::<Big>("2; 64; pow; dup; 1; +; swap; -"),["1.000 000 000"]);
+    assert_eq!(common::stack::<Big>("2; 70; pow; 2; 69; pow; /; 2; 70; pow; 3; /"),["2.000 000 000","393 530 540 239 137 079 296.000 000 000"]);
+    assert_eq!(common::stack_with_input::<Big>("input; 1; +","123456789012345678901234567890\n"),["123 456 789 012 345 678 901 234 567 891"]);
+}
+
+#[test]
+fn literals_keep_every_digit() {
+    assert_eq!(common::stack::<Big>("12345678901234567891; 1; +"),["12 345 678 901 234 567 892"]);
+    assert_eq!(common::stack::<Big>("9007199254740993; 9007199254740992; -"),["1.000 000 000"]);
+    assert_eq!(common::stack::<Big>("1e400; 1e399; /; 1e999999999"),["10.000 000 000","Inf"]);
+}
+
+#[test]
+fn comparisons() {
+    assert_eq!(common::stack::<Big>("2; 64; pow; dup; 1; +; lt; 2; 64; pow; 2; 32; pow; dup; *; eq"),["1","1"].map(|n| format!("{n}.000 000 000")));
+}
@@ -0,0 +1,53 @@
+use rpncalc::error::Error;
+use rpncalc::span::Span;
+
+/// The problems found in `script` when it starts with `depth` values on the stack, with where
+/// they point.
+fn problems(script: &str, depth: usize) -> Vec<(String,Span)> {
+    let chain = rpncalc::Chain::from_bare(script).unwrap_or_else(|e| panic!("{e}"));
+    rpncalc::check::check(&chain,depth).into_iter().map(|e| match e {
+        Error::At(span,e) => (e.to_string(),span),
+        e => panic!("{e} has no location")
+    }).collect()
+}
+
+#[test]
+fn balanced_scripts() {
+    assert_eq!(problems("1; 2; +; if [1] [2]; for(1, 3) [1; +]",0),[]);
+    assert_eq!(problems("+",2),[]);
+}
+
+#[test]
+fn underflow() {
+    let underflow = Error::Underflow(1,0).to_string();
+    assert_eq!(problems("1; if [drop] [drop; drop]",0),[
+        (underflow.clone(),Span::new(7,11)),
+        (underflow,Span::new(14,18))
+    ]);
+    assert_eq!(problems("1; +",0),[(Error::Underflow(2,1).to_string(),Span::new(3,4))]);
+}
+
+#[test]
+fn branch_depths() {
+    assert_eq!(problems("1; 2; if [drop] []",0),[
+        (Error::BranchDepths(-1,0).to_string(),Span::new(6,18))
+    ]);
+}
+
+#[test]
+fn loop_underflow() {
+    assert_eq!(problems("repeat(3) [drop]",2),[
+        (Error::LoopUnderflow(3,2).to_string(),Span::new(0,16))
+    ]);
+    // The index pushed by each iteration is dropped too
+    assert_eq!(problems("for(1, 3) [drop; drop]",2),[
+        (Error::LoopUnderflow(3,2).to_string(),Span::new(0,22))
+    ]);
+    assert_eq!(problems("for(1, 3) [drop; drop]",3),[]);
+}
+
+#[test]
+fn recursive_word_terminates() {
+    assert_eq!(problems("def down [dup; 0; gt; if [] [1; -; down]]; 5; down",0),[]);
+    assert_eq!(problems("def f [f]; f",0),[]);
+}
@@ -0,0 +1,90 @@
+//! Harness shared by the integration tests. Each test binary uses only part of it.
+#![allow(dead_code)]
+
+use rpncalc::Command;
+use rpncalc::context::Context;
+use rpncalc::error::Error;
+use rpncalc::number::Number;
+use rpncalc::value::Value;
+use rpncalc::vm::Code;
+
+/// Everything observable after running a script: its result, the stack, the registers and what
+/// it printed.
+#[derive(Debug,PartialEq)]
+pub struct Outcome {
+    pub result: Result<Option<String>,String>,
+    pub stack: Vec<String>,
+    pub registers: Vec<(String,String)>,
+    pub printed: String
+}
+
+/// Runs a chain parsed from `script` with the tree interpreter, or compiled for the VM, typing
+/// `7` when it asks for input.
+pub fn outcome(chain: rpncalc::Chain, script: &str, mut ctx: Context, vm: bool) -> Outcome {
+    let mut stack = vec![];
+    let mut printed = vec![];
+    let result = if vm {
+        Code::compile(&chain).comm(&mut stack,&mut ctx,&b"7\n"[..],&mut printed)
+    } else {
+        chain.comm(&mut stack,&mut ctx,&b"7\n"[..],&mut printed)
+    };
+    Outcome {
+        result: result.map_err(|e| e.with_source("<test>",script).to_string()),
+        stack: stack.iter().map(|v| v.to_string()).collect(),
+        registers: ctx.registers.iter().map(|(k,v)| (k.clone(),v.to_string())).collect(),
+        printed: String::from_utf8(printed).unwrap()
+    }
+}
+
+/// Runs a script with the backend `N` through both the tree interpreter and the VM, checking
+/// that they agree, and returns the stack from the bottom up or the error it failed with.
+pub fn run<N: Number>(script: &str) -> Result<Vec<Value<N>>,Error> {
+    run_with_input(script,"")
+}
+
+/// Runs a script as [`run`] does, with `input` to read from.
+fn run_with_input<N: Number>(script: &str, input: &str) -> Result<Vec<Value<N>>,Error> {
+    let chain = rpncalc::Chain::from_bare(script)?;
+    let [tree,vm] = [false,true].map(|vm| {
+        let mut stack = vec![];
+        let mut ctx: Context<N> = Context::default();
+        let result = if vm {
+            Code::compile(&chain).comm(&mut stack,&mut ctx,input.as_bytes(),std::io::sink())
+        } else {
+            chain.clone().comm(&mut stack,&mut ctx,input.as_bytes(),std::io::sink())
+        };
+        result.map(|_| stack)
+    });
+    let compared = |result: &Result<Vec<Value<N>>,Error>| result.as_ref().map(|stack| shown(stack)).map_err(|e| e.inner().to_string());
+    assert_eq!(compared(&tree),compared(&vm),"for script:\n{script}");
+    tree
+}
+
+/// The values of a stack as they are shown, without the padding in front.
+fn shown<N: Number>(stack: &[Value<N>]) -> Vec<String> {
+    stack.iter().map(|v| v.to_string().trim_start().to_string()).collect()
+}
+
+/// Runs a script that should fail, as [`run`] does, returning the error.
+pub fn error<N: Number>(script: &str) -> Error {
+    match run::<N>(script) {
+        Ok(stack) => panic!("expected an error, got {:?} for script:\n{script}",shown(&stack)),
+        Err(e) => e
+    }
+}
+
+/// Runs a script that should succeed, as [`run`] does, returning the stack as it is shown.
+pub fn stack<N: Number>(script: &str) -> Vec<String> {
+    stack_with_input::<N>(script,"")
+}
+
+/// Runs a script that should succeed with `input` to read from, returning the stack as it is
+/// shown.
+pub fn stack_with_input<N: Number>(script: &str, input: &str) -> Vec<String> {
+    shown(&run_with_input::<N>(script,input).unwrap())
+}
+
+/// Runs a script that should succeed, as [`run`] does, returning the numbers on the stack.
+pub fn nums<N: Number>(script: &str) -> Vec<N> {
+    run::<N>(script).unwrap().iter().map(|v| v.as_num().unwrap()).collect()
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use rpncalc::complex::Complex;
+
+#[test]
+fn literals() {
+    assert_eq!(common::stack::<Complex>("3+4i; (3,4); -2.5i; 1e-3-1i"),["3.000 000 000 + 4.000 000 000i","3.000 000 000 + 4.000 000 000i","0.000 000 000 - 2.500 000 000i","0.001 000 000 - 1.000 000 000i"]);
+    assert_eq!(common::stack_with_input::<Complex>("input; 2+0i","(1, -2)\n"),["1.000 000 000 - 2.000 000 000i","2.000 000 000"]);
+    assert_eq!(rpncalc::Chain::from_bare("(1,-2); 4i; 1.5+2i").unwrap().to_string(),"1-2i;\n4i;\n1.5+2i");
+}
+
+#[test]
+fn out_of_domain_results() {
+    assert_eq!(common::stack::<Complex>("-1; root2; -4; root2"),["0.000 000 000 + 1.000 000 000i","0.000 000 000 + 2.000 000 000i"]);
+    assert_eq!(common::stack::<Complex>("-1; loge; im; pi; eq"),["1.000 000 000"]);
+    assert_eq!(common::stack::<Complex>("-8; 1/3; pow"),["1.000 000 000 + 1.732 050 808i"]);
+    assert_eq!(common::stack::<Complex>("2; asin; sin; re"),["2.000 000 000"]);
+    assert_eq!(common::stack::<Complex>("1000; log10; 2; 0.5; pow; 1; 0; /"),["3.000 000 000","1.414 213 562","Inf"]);
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(common::stack::<Complex>("3+4i; 1-2i; *; 1+1i; /"),["4.500 000 000 - 6.500 000 000i"]);
+    assert_eq!(common::stack::<Complex>("0+1i; dup; *; -1; eq; 1+1i; 1+1i; gt"),["1.000 000 000","0.000 000 000"]);
+}
+
+#[test]
+fn parts() {
+    assert_eq!(common::stack::<Complex>("3+4i; re; 3+4i; im; 3+4i; abs; 3+4i; conj"),["3.000 000 000","4.000 000 000","5.000 000 000","3.000 000 000 - 4.000 000 000i"]);
+    assert_eq!(common::stack::<Complex>("0+2i; arg; pi; 2; /; eq; -1; arg"),["1.000 000 000","3.141 592 654"]);
+    assert_eq!(common::stack::<Complex>("2; pi; 2; /; polar; 1+1i; dup; abs; swap; arg; polar; 1+1i; -"),["0.000 000 000 + 2.000 000 000i","0.000 000 000"]);
+}
+
+#[test]
+fn real_polar() {
+    assert_eq!(common::stack::<f64>("2; 0; polar; 2; pi; polar; 2; -1; arg; polar; 2; 0; pi; -; polar"),["2.000 000 000","-  2.000 000 000","-  2.000 000 000","-  2.000 000 000"]);
+    assert_eq!(common::stack::<f64>("2; pi; 2; /; polar; 2; pi; 2; *; polar"),["NaN","NaN"]);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use rpncalc::decimal::{Decimal,Settings,Rounding,configure};
+use rpncalc::number::Number;
+
+#[test]
+fn exact_sums() {
+    configure(Settings { scale: 2, rounding: Rounding::HalfEven });
+    assert_eq!(common::stack::<Decimal>("0.1; 0.2; +; 0.3; eq"),["1.00"]);
+    assert_eq!(common::stack::<Decimal>("0; repeat(1000) [0.01; +]"),["10.00"]);
+    assert_eq!(common::stack::<Decimal>("1234567.5; 0.25; -"),["1 234 567.25"]);
+}
+
+#[test]
+fn rounding_modes() {
+    let script = "2.675; 1; *; -2.665; 1; *; 10; 3; /; -2; 3; /; input";
+    configure(Settings { scale: 2, rounding: Rounding::HalfEven });
+    assert_eq!(common::stack_with_input::<Decimal>(script,"19.999\n"),["2.68","-  2.66","3.33","-  0.67","20.00"]);
+    configure(Settings { scale: 2, rounding: Rounding::HalfUp });
+    assert_eq!(common::stack_with_input::<Decimal>(script,"19.999\n"),["2.68","-  2.67","3.33","-  0.67","20.00"]);
+    configure(Settings { scale: 2, rounding: Rounding::Truncate });
+    assert_eq!(common::stack_with_input::<Decimal>(script,"19.999\n"),["2.67","-  2.66","3.33","-  0.66","19.99"]);
+}
+
+#[test]
+fn scale() {
+    configure(Settings { scale: 0, rounding: Rounding::HalfEven });
+    assert_eq!(common::stack::<Decimal>("2.5; 3.5; 1; 3; /"),["2","4","0"]);
+    configure(Settings { scale: 4, rounding: Rounding::HalfEven });
+    assert_eq!(common::stack::<Decimal>("1; 3; /; 3; *; 2; root2; 1.05; 10; pow"),["0.999 9","1.414 2","1.628 9"]);
+    configure(Settings { scale: 2, rounding: Rounding::HalfEven });
+    assert_eq!(common::stack::<Decimal>("1; 0; /"),["Inf"]);
+}
+
+#[test]
+fn literals_keep_every_digit() {
+    configure(Settings { scale: 30, rounding: Rounding::HalfEven });
+    assert_eq!(common::stack::<Decimal>("0.123456789012345678901234567891; 1/7"),["0.123 456 789 012 345 678 901 234 567 891","0.142 857 142 857 142 857 142 857 142 857"]);
+    configure(Settings { scale: 2, rounding: Rounding::HalfEven });
+    assert_eq!(common::stack::<Decimal>("12345678901234567891.005; 1e-999999999; 1e999999999"),["12 345 678 901 234 567 891.00","0.00","Inf"]);
+}
+
+#[test]
+fn huge_exponents_in_input() {
+    configure(Settings { scale: 2, rounding: Rounding::HalfEven });
+    assert_eq!(common::stack_with_input::<Decimal>("input","1e999999999\n"),["Inf"]);
+    assert_eq!(common::stack_with_input::<Decimal>("input","1e-999999999\n"),["0.00"]);
+}
+
+#[test]
+fn values_keep_their_scale() {
+    configure(Settings { scale: 4, rounding: Rounding::HalfEven });
+    let third = Decimal::from_ratio(1.0,3.0);
+    configure(Settings { scale: 2, rounding: Rounding::HalfEven });
+    assert_eq!(third.format().trim_start(),"0.333 3");
+    assert_eq!((third.clone() + Decimal::from_f64(1.0)).format().trim_start(),"1.333 3");
+    assert_eq!((third.clone() * Decimal::from_f64(2.5)).format().trim_start(),"0.833 2");
+    assert_eq!((Decimal::from_f64(1.0) / third.clone()).format().trim_start(),"3.000 3");
+    assert!(third > Decimal::from_f64(0.33) && third < Decimal::from_f64(0.34));
+}
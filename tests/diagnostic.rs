@@ -0,0 +1,36 @@
+use rpncalc::Chain;
+
+fn diagnostic(source: &str) -> String {
+    Chain::from_bare(source).err().expect("source should not parse").with_source("script.rpncalc",source).to_string()
+}
+
+#[test]
+fn caret_under_span() {
+    assert_eq!(diagnostic("1;\n2; R(1 foo); 3"),"\
+Expected ',' or ')', found foo
+ --> script.rpncalc:2:8
+  |
+2 | 2; R(1 foo); 3
+  |        ^^^");
+}
+
+#[test]
+fn caret_at_end_of_input() {
+    assert_eq!(diagnostic("R("),"\
+Expected an atom, found end of input
+ --> script.rpncalc:1:3
+  |
+1 | R(
+  |   ^");
+}
+
+#[test]
+fn gutter_widens_with_line_number() {
+    let source = "1;\n".repeat(9) + "\"é";
+    assert_eq!(diagnostic(&source),"\
+String is missing its closing quote
+  --> script.rpncalc:10:1
+   |
+10 | \"é
+   | ^^");
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use rpncalc::Chain;
+use rpncalc::rational::Rational;
+
+fn format(source: &str) -> String {
+    Chain::from_bare(source).ok().unwrap().to_string()
+}
+
+const SCRIPTS: &[&str] = &[
+    "1;2;  SWAP; 1/3; +",
+    "# squares\nDEF sq [DUP;*];  3; sq ; /* twice */ sq",
+    "0; REPEAT(3) [1; +]; for(1, 4) n [rcl n; *]; 5; sto x; rcl x",
+    "1; IF [10; 20; 30; 40; 50; 60; 70; 80; 90; 100; 110; 120; 130] [TRY [0] [1]]; # done\ndisp \"sum = {0}\\n\"",
+    "[1; # one\n2]; exec; +; 1.50; 2.0e3; -7"
+];
+
+#[test]
+fn canonical_source() {
+    assert_eq!(format(SCRIPTS[0]),"1;\n2;\nswap;\n1/3;\n+");
+    assert_eq!(format(SCRIPTS[1]),"# squares\ndef sq [ dup; * ];\n3;\nsq;\n/* twice */\nsq");
+    assert_eq!(format(SCRIPTS[4]),"[\n    1; # one\n    2\n];\nexec;\n+;\n1.5;\n2000;\n-7");
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for script in SCRIPTS {
+        let formatted = format(script);
+        assert_eq!(format(&formatted),formatted,"for script:\n{script}");
+    }
+}
+
+#[test]
+fn formatted_scripts_run_the_same() {
+    for script in SCRIPTS {
+        assert_eq!(common::stack::<Rational>(&format(script)),common::stack::<Rational>(script),"for script:\n{script}");
+    }
+}
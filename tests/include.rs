@@ -0,0 +1,48 @@
+mod common;
+
+use rpncalc::context::Context;
+
+/// Writes `files` into a fresh directory named after the test, and runs `main` from it as the
+/// tui does, through both the tree interpreter and the VM.
+fn outcomes(test: &str, files: &[(&str,&str)], main: &str) -> [common::Outcome;2] {
+    let dir = std::env::temp_dir().join(format!("rpncalc-include-{test}-{}",std::process::id()));
+    for (name,source) in files {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path,source).unwrap();
+    }
+    let path = std::fs::canonicalize(dir.join(main)).unwrap();
+    let source = std::fs::read_to_string(&path).unwrap();
+    let outcomes = [false,true].map(|vm| {
+        let mut ctx = Context::default();
+        ctx.files.push(path.clone());
+        common::outcome(rpncalc::Chain::from_bare(&source).ok().unwrap(),&source,ctx,vm)
+    });
+    std::fs::remove_dir_all(&dir).unwrap();
+    outcomes
+}
+
+#[test]
+fn included_words_and_registers() {
+    let [tree,vm] = outcomes("words",&[
+        ("main.rpn","2; include \"lib/sq.rpn\"; sq; rcl y"),
+        ("lib/sq.rpn","def sq [dup; *]; 10; sto y")
+    ],"main.rpn");
+    assert_eq!(tree,vm);
+    assert_eq!(tree.result,Ok(None));
+    assert_eq!(tree.stack,["   4.000 000 000","  10.000 000 000"]);
+}
+
+#[test]
+fn include_cycle() {
+    let [tree,vm] = outcomes("cycle",&[
+        ("a.rpn","1; include \"b.rpn\""),
+        ("b.rpn","2; include \"a.rpn\"")
+    ],"a.rpn");
+    assert_eq!(tree,vm);
+    assert_eq!(tree.stack,["   1.000 000 000","   2.000 000 000"]);
+    let error = tree.result.unwrap_err();
+    assert!(error.starts_with("Include cycle: "),"{error}");
+    let files = error.lines().next().unwrap()["Include cycle: ".len()..].split(" -> ").map(|f| f.rsplit(['/','\\']).next().unwrap()).collect::<Vec<_>>();
+    assert_eq!(files,["a.rpn","b.rpn","a.rpn"]);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use rpncalc::interval::Interval;
+
+fn holds(interval: Interval, exact: f64, width: f64) -> bool {
+    interval.lo <= exact && exact <= interval.hi && interval.hi - interval.lo <= width
+}
+
+#[test]
+fn literals() {
+    assert_eq!(common::nums::<Interval>("2; 1/4"),[Interval { lo: 2.0, hi: 2.0 },Interval { lo: 0.25, hi: 0.25 }]);
+    let [tenth,range] = common::nums::<Interval>("0.1; 1.2±0.05")[..] else { panic!() };
+    assert!(tenth.lo < 0.1 && 0.1 < tenth.hi);
+    assert!(range.lo < 1.15 && 1.25 < range.hi && range.hi - range.lo < 0.1 + 1e-15);
+    assert_eq!(common::nums::<Interval>("1.2+-0.05"),common::nums::<Interval>("1.2±0.05"));
+}
+
+#[test]
+fn literals_with_more_digits_than_floats() {
+    let big = 9007199254740992f64;
+    let [odd,even,third] = common::nums::<Interval>("9007199254740993; 9007199254740992; 1/9007199254740993")[..] else { panic!() };
+    assert_eq!(odd,Interval { lo: big.next_down(), hi: big.next_up() });
+    assert_eq!(even,Interval { lo: big, hi: big });
+    assert!(third.lo < third.hi && holds(third,1.0 / big,1e-30));
+    let [tiny,huge] = common::nums::<Interval>("1e-999999999; 1e999999999")[..] else { panic!() };
+    assert!(tiny.lo < 0.0 && 0.0 < tiny.hi);
+    assert_eq!(huge,Interval { lo: f64::MAX, hi: f64::INFINITY });
+    assert_eq!(common::nums::<Interval>("inf")[0],Interval { lo: f64::INFINITY, hi: f64::INFINITY });
+}
+
+#[test]
+fn arithmetic_rounds_outwards() {
+    let [sum,product,quotient] = common::nums::<Interval>("0.1; 0.2; +; 1.2±0.05; 3; *; 1; 3; /")[..] else { panic!() };
+    assert!(holds(sum,0.3,1e-15));
+    assert!(holds(product,3.45,0.3 + 1e-14) && holds(product,3.75,0.3 + 1e-14));
+    assert!(holds(quotient,1.0 / 3.0,1e-15) && quotient.lo < quotient.hi);
+    assert_eq!(common::nums::<Interval>("1±1; 2; /; 1; 0±1; /")[1],Interval { lo: f64::NEG_INFINITY, hi: f64::INFINITY });
+    assert_eq!(common::nums::<Interval>("2; 3; *; 7; +"),[Interval { lo: 13.0, hi: 13.0 }]);
+}
+
+#[test]
+fn functions() {
+    let [root,log,power] = common::nums::<Interval>("2; root2; 1±0.5; loge; -2±1; 2; pow")[..] else { panic!() };
+    assert!(holds(root,2f64.sqrt(),1e-15));
+    assert!(holds(log,0.5f64.ln(),1.1) && holds(log,1.5f64.ln(),1.1));
+    assert!(power.lo <= 1.0 && power.hi >= 9.0 && power.hi < 9.0 + 1e-12);
+    let [sin,cos,tan] = common::nums::<Interval>("1.5±0.2; sin; 3±0.5; cos; 1.5±0.1; tan")[..] else { panic!() };
+    assert!(sin.hi == 1.0 && holds(sin,1.3f64.sin(),0.1));
+    assert!(cos.lo == -1.0 && cos.hi >= 2.5f64.cos());
+    assert_eq!(tan,Interval { lo: f64::NEG_INFINITY, hi: f64::INFINITY });
+    assert!(common::nums::<Interval>("-2; root2")[0].lo.is_nan());
+}
+
+#[test]
+fn comparisons_hold_for_every_number() {
+    let cmp = |script| common::nums::<Interval>(script).into_iter().map(|i| i.lo).collect::<Vec<_>>();
+    assert_eq!(cmp("1±0.1; 2; lt; 1±0.1; 1.05; lt; 1±0.1; 1.05; gte"),[1.0,0.0,0.0]);
+}
+
+#[test]
+fn display() {
+    let show = |script| common::nums::<Interval>(script).iter().map(|i| rpncalc::number::Number::format(i).trim_start().to_string()).collect::<Vec<_>>();
+    assert_eq!(show("1.2±0.05; 2"),["[1.149 999 999, 1.250 000 001]","2.000 000 000"]);
+    assert!(matches!(rpncalc::Chain::from_bare("1.2±0.05").map(|c| c.to_string()),Ok(s) if s.contains("1.2±0.05")));
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use rpncalc::context::Context;
+use rpncalc::optimize::optimize;
+use common::outcome;
+
+/// Checks that optimizing a script gives `expected` back when printed, and that it runs the same.
+fn assert_optimized(script: &str, expected: &str) {
+    let chain = rpncalc::Chain::from_bare(script).unwrap();
+    let optimized = optimize::<f64>(chain.clone());
+    assert_eq!(optimized.to_string(),expected,"for script:\n{script}");
+    assert_eq!(outcome(chain,script,Context::default(),false),outcome(optimized,script,Context::default(),false),"for script:\n{script}");
+}
+
+#[test]
+fn folding() {
+    assert_optimized("2; 3; +; sin",&rpncalc::Chain::from_bare(&5f64.sin().to_string()).unwrap().to_string());
+    assert_optimized("1; 2; 3; *; +; pi; *; 4",&format!("{};\n4",rpncalc::Chain::from_bare(&(7.0 * std::f64::consts::PI).to_string()).unwrap()));
+    assert_optimized("dup; 2; *","dup;\n2;\n*");
+    assert_optimized("1; +","1;\n+");
+    assert_optimized("input; 1; 2; +; +; p; 2; 2; *","input;\n3;\n+;\nprint;\n4");
+}
+
+#[test]
+fn empty_statements() {
+    assert_optimized("1;; 2;;;","1;\n2");
+    assert_optimized(";;","");
+    assert_optimized("1; p;","1;\nprint;\n");
+    assert_optimized("1; p;; 2","1;\nprint;\n2");
+}
+
+#[test]
+fn dead_branches() {
+    assert_optimized("0; if [1; 2; +] [p]","3");
+    assert_optimized("1; if [p] [disp \"x\"; 4]","display \"x\";\n4");
+    assert_optimized("dup; if [1] [2]","dup;\nif [ 1 ] [ 2 ]");
+    assert_optimized("0; if [p;] [2]; 3","print;\n3");
+    assert_optimized("5; 0; if [p;] [2]","5;\nprint;\n");
+    assert_optimized("1; p; 0; if [] [2]","1;\nprint;\n");
+    assert_optimized("# pick\n1; if [p] [2]","# pick\n2");
+}
+
+#[test]
+fn empty_branch_has_no_output() {
+    let script = "1; p; 0; if [] [2]";
+    let optimized = optimize::<f64>(rpncalc::Chain::from_bare(script).unwrap());
+    let outcome = outcome(optimized,script,Context::default(),false);
+    assert_eq!(outcome.printed,"   1.000 000 000\n");
+    assert_eq!(outcome.result,Ok(None));
+}
+
+#[test]
+fn nested() {
+    assert_optimized("def f [2; 2; +]; f; R(3) [1; 1; +]","def f [ 4 ];\nf;\nrepeat(3) [ 2 ]");
+    assert_optimized("[1; 2; -]; exec; while [0] [p]","[ -1 ];\nexec;\nwhile [ 0 ] [ print ]");
+    assert_optimized("for(1, 3) [1; if [] [10; *]; p]","for(1, 3) [ 10; *; print ]");
+}
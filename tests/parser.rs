@@ -0,0 +1,66 @@
+use rpncalc::ast::{Atom,Block,Program,Stmt};
+use rpncalc::error::Error;
+use rpncalc::lexer::{Lexer,Token};
+use rpncalc::parser::parse;
+use rpncalc::span::Span;
+
+fn tokens(source: &str) -> Vec<(Token,Span)> {
+    Lexer::new(source).collect::<Result<_,_>>().unwrap()
+}
+
+#[test]
+fn tokens_and_spans() {
+    assert_eq!(tokens("1.5 dup;[ R(a, 2) ] \"s\""),vec![
+        (Token::Num(1.5,"1.5".to_string()),Span::new(0,3)),
+        (Token::Word("dup".to_string()),Span::new(4,7)),
+        (Token::Semi,Span::new(7,8)),
+        (Token::LBracket,Span::new(8,9)),
+        (Token::Word("R".to_string()),Span::new(10,11)),
+        (Token::LParen,Span::new(11,12)),
+        (Token::Word("a".to_string()),Span::new(12,13)),
+        (Token::Comma,Span::new(13,14)),
+        (Token::Num(2.0,"2".to_string()),Span::new(15,16)),
+        (Token::RParen,Span::new(16,17)),
+        (Token::RBracket,Span::new(18,19)),
+        (Token::Str("s".to_string()),Span::new(20,23))
+    ]);
+}
+
+#[test]
+fn program() {
+    let word = |w: &str| Atom::Word(w.to_string());
+    assert_eq!(parse("1; if [2] []; ;R(x)").unwrap(),Program(vec![
+        Stmt(vec![Atom::Num(1.0,"1".to_string())],Span::new(0,1),vec![]),
+        Stmt(vec![
+            word("if"),
+            Atom::Block(Block(vec![Stmt(vec![Atom::Num(2.0,"2".to_string())],Span::new(7,8),vec![])])),
+            Atom::Block(Block(vec![Stmt(vec![],Span::new(11,11),vec![])]))
+        ],Span::new(3,12),vec![]),
+        Stmt(vec![],Span::new(13,13),vec![]),
+        Stmt(vec![word("R"),Atom::Group(vec![word("x")])],Span::new(15,19),vec![])
+    ]));
+}
+
+#[test]
+fn syntax_errors() {
+    let error = |source| match parse(source).unwrap_err() {
+        Error::At(span,e) => (span,e.to_string()),
+        e => panic!("{e} has no location")
+    };
+    assert_eq!(error("1; [2"),(Span::new(3,4),"Unbalanced Braces".to_string()));
+    assert_eq!(error("1]"),(Span::new(1,2),"Unbalanced Braces".to_string()));
+    assert_eq!(error("R(1 2)"),(Span::new(4,5),"Expected ',' or ')', found 2".to_string()));
+    assert_eq!(error("\"abc"),(Span::new(0,4),"String is missing its closing quote".to_string()));
+}
+
+#[test]
+fn trailing_comments() {
+    let comments = |source| parse(source).unwrap().0.into_iter().map(|s| s.2.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ")).collect::<Vec<_>>();
+    assert_eq!(comments("1; # one\n2"),["# one",""]);
+    assert_eq!(comments("1; /* one */\n# two\n2"),["/* one */","# two"]);
+    assert_eq!(comments("1; /* two */ 2"),["","/* two */"]);
+    assert_eq!(comments("1; /* one */ # one\n2"),["/* one */ # one",""]);
+    let source = "# first\n1; # one\n2; # two\nif [3; # three\n4] []";
+    let printed = rpncalc::Chain::from_bare(source).ok().unwrap().to_string();
+    assert_eq!(printed,"# first\n1; # one\n2; # two\nif [\n    3; # three\n    4\n] []");
+}
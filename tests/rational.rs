@@ -0,0 +1,53 @@
+mod common;
+
+use rpncalc::rational::Rational;
+
+#[test]
+fn exact_arithmetic() {
+    assert_eq!(common::stack::<Rational>("0.1; 0.2; +; 0.3; eq"),["1"]);
+    assert_eq!(common::stack::<Rational>("1; 3; /; 3; *"),["1"]);
+    assert_eq!(common::stack::<Rational>("2; 3; /; 1/6; -; 1/2; eq"),["1"]);
+    assert_eq!(common::stack::<Rational>("1/3; 1/4; gt; -1/3; -1/4; gt"),["1","0"]);
+    assert_eq!(common::stack_with_input::<Rational>("input","2/6\n"),["1/3"]);
+}
+
+#[test]
+fn literals_keep_every_digit() {
+    assert_eq!(common::stack::<Rational>("0.30000000000000001; 0.3; eq"),["0"]);
+    assert_eq!(common::stack::<Rational>("123456789012345678901/100000000000000000000; 1.23456789012345678901; eq"),["1"]);
+    assert_eq!(common::stack::<Rational>("1e-30; 1e30; *"),["1"]);
+    let chain = rpncalc::Chain::from_bare("0.30000000000000001; 1.50").unwrap();
+    assert_eq!(chain.to_string(),"0.30000000000000001;\n1.5");
+}
+
+#[test]
+fn huge_exponents_fall_back() {
+    assert_eq!(common::stack::<Rational>("1e-999999999; 0; eq; 1e999999999"),["1","Inf"]);
+    assert_eq!(common::stack_with_input::<Rational>("input","1e-999999999\n"),["0.000 000 000"]);
+}
+
+#[test]
+fn display() {
+    assert_eq!(common::stack::<Rational>("1/3; 4/3; -7/2; 1200; 6/4"),["1/3","1 1/3","-  3 1/2","1 200","1 1/2"]);
+    assert_eq!(common::stack::<Rational>("2; 100; pow"),["1 267 650 600 228 229 401 496 703 205 376"]);
+    assert_eq!(common::stack::<Rational>("2; 3; -; -2; pow"),["1"]);
+    assert_eq!(common::stack::<Rational>("1; 0; /"),["Inf"]);
+}
+
+#[test]
+fn irrational_fallback() {
+    assert_eq!(common::stack::<Rational>("9/4; root2; 2; root2"),["1 1/2","1.414 213 562"]);
+    assert_eq!(common::stack::<Rational>("0; sin; 1; sin; 1; +"),["0","1.841 470 985"]);
+    assert_eq!(common::stack::<Rational>("pi; 2; *"),["6.283 185 307"]);
+}
+
+#[test]
+fn loops_step_exactly() {
+    assert_eq!(common::stack::<Rational>("for(0, 0.5, 0.1) []; +; +; +; +; +"),["1 1/2"]);
+}
+
+#[test]
+fn folding_keeps_exact_results() {
+    let chain = rpncalc::Chain::from_bare("0.1; 0.2; +; 1; 3; /; 2/4; 2; *").unwrap();
+    assert_eq!(rpncalc::optimize::optimize::<Rational>(chain).to_string(),"0.3;\n1;\n3;\n/;\n1");
+}
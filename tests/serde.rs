@@ -0,0 +1,13 @@
+#![cfg(feature = "serde")]
+
+use rpncalc::Chain;
+
+#[test]
+fn chain_round_trips_through_json() {
+    let source = "# squares\ndef sq [dup; *]; 3; sq; 1/3; +; 2+3i; d; 1.5±0.1; d; 2026-10-17; d; disp \"s = {0}\";\nfor(1, 3) n [try [rcl n] [1]; if [2] []]; # done\n[[1 2] [3 4]]; 5 km";
+    let chain = Chain::from_bare(source).unwrap_or_else(|e| panic!("{}",e.with_source("<test>",source)));
+    let json = serde_json::to_string(&chain).unwrap();
+    let read: Chain = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.to_string(),chain.to_string());
+    assert_eq!(serde_json::to_string(&read).unwrap(),json);
+}
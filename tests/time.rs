@@ -0,0 +1,42 @@
+mod common;
+
+use rpncalc::error::Error;
+use rpncalc::rational::Rational;
+
+#[test]
+fn literals() {
+    assert_eq!(common::stack::<Rational>("2026-10-17; 2026-10-17T14:30:15; 14:30; 1h30m; 45s; 1w"),["2026-10-17","2026-10-17 14:30:15","14 1/2 h","1 1/2 h","45 s","7 d"]);
+    assert_eq!(rpncalc::Chain::from_bare("2024-02-29T09:05; 9:05; 1w2h; 1.5s").unwrap().to_string(),"2024-02-29T09:05;\n09:05;\n7d2h;\n1.5s");
+    for date in ["2026-02-30","2026-13-01","2026-02-29T10:00"] {
+        let script = format!("1; {date}");
+        match rpncalc::Chain::from_bare(&script) {
+            Err(Error::At(span,e)) => assert!(span == rpncalc::span::Span::new(3,script.len()) && matches!(*e,Error::DateRange(_)),"{e} for {date}"),
+            _ => panic!("{date} is accepted")
+        }
+    }
+}
+
+#[test]
+fn date_arithmetic() {
+    assert_eq!(common::stack::<Rational>("2026-12-25; 2026-10-17; -"),["69 d"]);
+    assert_eq!(common::stack::<Rational>("2026-10-17; 1h30m; +; 36 h; 2026-10-17; +; 2026-10-17; 1 d; -"),["2026-10-17 01:30","2026-10-18 12:00","2026-10-16"]);
+    assert_eq!(common::stack::<Rational>("2026-10-17; 14:30; +; 2026-10-17; 14:30; +; 2026-10-18; -; to min"),["2026-10-17 14:30","-570 min"]);
+    assert_eq!(common::stack::<Rational>("2026-10-17; 2026-10-18; lt"),["1"]);
+    assert!(matches!(common::error::<Rational>("2026-10-17; 2026-10-17; +").inner(),Error::Type("duration","date")));
+    assert!(matches!(common::error::<Rational>("2026-10-17; 5; +").inner(),Error::Type("duration","number")));
+    assert!(matches!(common::error::<Rational>("2026-10-17; 1 m; +").inner(),Error::Units(_,_)));
+    assert!(matches!(common::error::<Rational>("2026-10-17; 2; *").inner(),Error::Type("number","date")));
+}
+
+#[test]
+fn calendar() {
+    assert_eq!(common::stack::<Rational>("2026-10-17; weekday; 1970-01-01; weekday; 2000-01-02; weekday"),["6","4","7"]);
+    assert_eq!(common::stack::<Rational>("2026-01-31; 1; addmonths; 2024-01-31; 1; addmonths; 2026-10-17T08:00; -10; addmonths"),["2026-02-28","2024-02-29","2025-12-17 08:00"]);
+    assert_eq!(common::stack::<Rational>("2026-10-17; toepoch; 0; fromepoch; 1800000000; fromepoch"),["1 792 195 200","1970-01-01","2027-01-15 08:00"]);
+    assert!(matches!(common::error::<Rational>("2026-10-17; 1/2; addmonths").inner(),Error::Expected(_,_)));
+    assert!(matches!(common::error::<Rational>("5; weekday").inner(),Error::Type("date","number")));
+    assert!(matches!(common::error::<Rational>("2026-01-01; 1e300 s; +; weekday").inner(),Error::Expected(_,_)));
+    assert!(matches!(common::error::<Rational>("2026-01-01; 1e300 s; +; 1; addmonths").inner(),Error::Expected(_,_)));
+    assert!(matches!(common::error::<Rational>("2026-01-01; 1e300; addmonths").inner(),Error::Expected(_,_)));
+    assert!(matches!(common::error::<Rational>("2026-01-01; 12000000; addmonths").inner(),Error::Expected(_,_)));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use rpncalc::number::Number;
+use rpncalc::uncertainty::Uncertain;
+
+fn close(lhs: f64, rhs: f64) -> bool {
+    (lhs - rhs).abs() <= 1e-12 * rhs.abs().max(1.0)
+}
+
+#[test]
+fn arithmetic() {
+    let [sum,product,quotient] = common::nums::<Uncertain>("10±0.3; 5±0.4; +; 2±0.1; 3±0.2; *; 6±0.3; 3; /")[..] else { panic!() };
+    assert!(close(sum.value,15.0) && close(sum.sigma,0.5));
+    assert!(close(product.value,6.0) && close(product.sigma,(0.3f64.powi(2) + 0.4f64.powi(2)).sqrt()));
+    assert!(close(quotient.value,2.0) && close(quotient.sigma,0.1));
+    assert_eq!(common::nums::<Uncertain>("2; 3; *; 1; +"),[Uncertain { value: 7.0, sigma: 0.0 }]);
+}
+
+#[test]
+fn functions() {
+    let [square,log,sin] = common::nums::<Uncertain>("3±0.1; 2; pow; 10±0.5; loge; 0±0.01; sin")[..] else { panic!() };
+    assert!(close(square.value,9.0) && close(square.sigma,0.6));
+    assert!(close(log.value,10f64.ln()) && close(log.sigma,0.05));
+    assert!(close(sin.value,0.0) && close(sin.sigma,0.01));
+    let [exp] = common::nums::<Uncertain>("2; 3±0.1; pow")[..] else { panic!() };
+    assert!(close(exp.value,8.0) && close(exp.sigma,8.0 * 2f64.ln() * 0.1));
+}
+
+#[test]
+fn display() {
+    let show = |script| common::nums::<Uncertain>(script).iter().map(|n| n.format().trim_start().to_string()).collect::<Vec<_>>();
+    assert_eq!(show("12.34±0.41; 9.8765±0.0123; -1234.5±96; 2"),["12.3 ± 0.4","9.877 ± 0.012","-  1 230 ± 100","2.000 000 000"]);
+    assert_eq!(show("1.26±0.96"),["1.3 ± 1.0"]);
+}
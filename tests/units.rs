@@ -0,0 +1,41 @@
+mod common;
+
+use rpncalc::error::Error;
+
+#[test]
+fn dimensions_combine() {
+    assert_eq!(common::stack::<f64>("9.81 m/s^2; 80 kg; *"),["784.800 000 000 m*kg/s^2"]);
+    assert_eq!(common::stack::<f64>("5 km; 2 h; /; 2; *"),["5.000 000 000 km/h"]);
+    assert_eq!(common::stack::<f64>("3 m; 2 m; *; 2 m; /"),["3.000 000 000 m"]);
+    assert_eq!(common::stack::<f64>("1 ft; 1 in; /; 2 m; 50 cm; /"),["12.000 000 000","4.000 000 000"]);
+}
+
+#[test]
+fn sums_convert_to_the_first_unit() {
+    assert_eq!(common::stack::<f64>("1 km; 250 m; +; 1 m; 1 ft; -"),["1.250 000 000 km","0.695 200 000 m"]);
+    assert_eq!(common::stack::<f64>("1 km; 999 m; gt; 1 h; 60 min; eq"),["1.000 000 000","1.000 000 000"]);
+}
+
+#[test]
+fn conversions() {
+    assert_eq!(common::stack::<f64>("60 mi/h; to km/h"),["96.560 640 000 km/h"]);
+    assert_eq!(common::stack::<f64>("9.81 m/s^2; 80 kg; *; to N; 1 kWh; to J"),["784.800 000 000 N","3 600 000.000 000 000 J"]);
+    assert_eq!(common::stack::<f64>("1 L; to m^3; 1 psi; to kPa"),["0.001 000 000 m^3","6.894 757 293 kPa"]);
+}
+
+#[test]
+fn incompatible_units() {
+    assert!(matches!(common::error::<f64>("1 m; 1 s; +").inner(),Error::Units(lhs,rhs) if lhs == "m" && rhs == "s"));
+    assert!(matches!(common::error::<f64>("1 m; 1; lt").inner(),Error::Units(_,_)));
+    assert!(matches!(common::error::<f64>("1 m; to kg").inner(),Error::Units(_,_)));
+    assert!(matches!(common::error::<f64>("1 m; sin").inner(),Error::Type("number","quantity")));
+    assert!(matches!(common::error::<f64>("1 furlong").inner(),Error::UnknownUnit(unit) if unit == "furlong"));
+}
+
+#[test]
+fn powers_are_bounded() {
+    assert_eq!(common::stack::<f64>("1 J^64; 1 J^-64; *; 2 m^100; 1 m^100; +"),["1.000 000 000","3.000 000 000 m^100"]);
+    assert!(matches!(common::error::<f64>("1 m^128").inner(),Error::UnknownUnit(unit) if unit == "m^128"));
+    assert!(matches!(common::error::<f64>("1 m^2000000000").inner(),Error::UnknownUnit(_)));
+    assert!(matches!(common::error::<f64>("1 m^100; 1 m; *").inner(),Error::UnknownUnit(unit) if unit == "m^101"));
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use rpncalc::context::Context;
+use common::outcome;
+
+fn assert_same(script: &str) {
+    assert_same_with_depth(script,1_000);
+}
+
+fn assert_same_with_depth(script: &str, max_depth: usize) {
+    let run = |vm| outcome(rpncalc::Chain::from_bare(script).unwrap(),script,Context { max_depth, ..Context::default() },vm);
+    assert_eq!(run(false),run(true),"for script:\n{script}");
+}
+
+#[test]
+fn straight_line() {
+    assert_same("1; 2; +; 3; *; dup; swap; p; 4; 5; swap 0 1; rev; d; dup 3");
+    assert_same("pi; sin; e; logE; 2; 10; pow; 1; 0; /; inf; 2; 3; gte");
+    assert_same("1; +");
+    assert_same("input; 1; +; disp \"got {0}\"");
+    assert_same("");
+}
+
+#[test]
+fn conditionals() {
+    assert_same("0; if [1] [2]; 5; if [10; p] [20; p]");
+    assert_same("if [1] [2]");
+    assert_same("try [1; +] [disp \"caught\"]; try [2] [3]");
+    assert_same("try [try [break] [1; +]] [disp \"outer\"]; 9");
+    assert_same("1; 2; try [+; break] [p]");
+}
+
+#[test]
+fn loops() {
+    assert_same("0; R(5) [1; +]; R(0) [p]");
+    assert_same("R(3) dup; R(2) 5");
+    assert_same("0; R [1; +; dup; 100; gt; if [] [break]]");
+    assert_same("10; while [dup; 0; gt] [dup; p; 1; -]");
+    assert_same("0; while [1] [1; +]");
+    assert_same("while [] []");
+    assert_same("for(1, 5) [dup; *; p]; for(10, 1, -3) i [rcl i; p]; for(1, 0) [p]");
+    assert_same("for(1, 3) [for(1, 2) j [rcl j; *; p]]");
+    assert_same("for(1, 3) i [for(1, 2) j [rcl i; 2; eq; if [] [break]]]");
+    assert_same("for(0, 1e9) [d]");
+    assert_same("R(3) [for(1, 2) [try [d; d] [1]]]; R [for(1, 2) [d; d]]");
+    assert_same("R(4) [disp \"x\"]; R(2) [try [break] [disp \"y\"]]");
+}
+
+#[test]
+fn words() {
+    assert_same("def sq [dup; *]; 3; sq; sq; p");
+    assert_same("def fact [dup; 1; gt; if [d; 1] [dup; 1; -; fact; *]]; 10; fact");
+    assert_same("def down [dup; 0; gt; if [] [1; -; down]]; 50; down");
+    assert_same_with_depth("def down [dup; 0; gt; if [] [1; -; down]]; 50; down",10);
+    assert_same_with_depth("def f [f]; try [f] [disp \"deep\"]; def g [1; try [g] []]; g",20);
+    assert_same("def f [1]; f; def f [2]; f; nope");
+    assert_same("def f [for(1, 3) [break]]; for(1, 2) i [f]");
+    assert_same("def w [R [d]]; 1; 2; 3; w; 4");
+}
+
+#[test]
+fn registers_and_quotations() {
+    assert_same("5; sto x; rcl x; rcl x; +; sto y; vars; purge x; rcl x");
+    assert_same("[dup; *]; sto sq; 4; rcl sq; exec; 1; [10; +]; dip; 2; [3; *]; keep");
+    assert_same("6; [1; +]; [2; *]; bi; [def q [7]]; exec; q");
+    assert_same("for(1, 3) n [disp \"n = {n}, top = {0}\"; rcl n]");
+}